@@ -0,0 +1,50 @@
+// Build script
+// Captures build metadata exposed by the /info endpoint
+
+use std::process::Command;
+
+fn main() {
+    // Prefer an explicit GIT_SHA (e.g. passed as a Docker build arg, where .git is unavailable)
+    let git_sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| command_output("git", &["rev-parse", "--short", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rust_version = command_output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+
+    let build_time = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    println!("cargo:rustc-env=GIT_SHA={}", git_sha);
+    println!("cargo:rustc-env=BUILD_TIME={}", build_time);
+    println!("cargo:rustc-env=RUST_VERSION={}", rust_version);
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    for path in git_ref_paths() {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+}
+
+/// コミットで変わる `.git` 内のファイル。`.git/HEAD` はブランチを切り替えたときしか変わらないため、
+/// 今のブランチの ref (まとめられていれば `packed-refs`) と `index` も見る。
+/// 存在しないパスを渡すと毎回ビルドし直すことになるので、あるものだけを返す。
+fn git_ref_paths() -> Vec<String> {
+    let mut paths = vec![".git/refs/heads".to_string(), ".git/packed-refs".to_string(), ".git/index".to_string()];
+    if let Ok(head) = std::fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            paths.push(format!(".git/{}", reference));
+        }
+    }
+    paths.into_iter().filter(|path| std::path::Path::new(path).exists()).collect()
+}
+
+/// コマンドを実行し、成功した場合だけ標準出力 (前後の空白を除去) を返す。
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.trim().to_string()).filter(|s| !s.is_empty())
+}
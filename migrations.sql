@@ -1,31 +1,71 @@
 -- Database migrations for word-rest-api
 -- This script creates all necessary tables and indexes
 
--- Enable UUID extension
+-- Optional: IDs are generated by the application, so this may fail on roles that
+-- can't create extensions without affecting the rest of the script
 CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
 
 -- Create users table
 CREATE TABLE IF NOT EXISTS users (
-    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    id UUID PRIMARY KEY,
     name VARCHAR(255) NOT NULL,
-    email VARCHAR(255) UNIQUE NOT NULL,
+    email VARCHAR(255) NOT NULL,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ,
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE
 );
 
+-- IDs are supplied by the application; drop the uuid_generate_v4() default on existing databases
+ALTER TABLE users ALTER COLUMN id DROP DEFAULT;
+
+-- Soft delete support for existing databases
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+
+-- Email verification flag for existing databases (existing users start out unverified)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT FALSE;
+
 -- Create index on email for users table
 CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
 
+-- Email must be unique among active (not soft-deleted) users only
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_active ON users(email) WHERE deleted_at IS NULL;
+
+-- Optional: only GET /api/vocabulary/:id/related needs trigram similarity(); like uuid-ossp,
+-- this may fail on roles that can't create extensions (pg_trgm is available on Neon)
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Recommended once the users table grows: GET /api/users/search matches name/email with
+-- ILIKE '%q%', which can't use a B-tree index. A trigram index (needs pg_trgm above) makes those lookups indexed.
+-- CREATE INDEX IF NOT EXISTS idx_users_name_trgm ON users USING gin (name gin_trgm_ops);
+-- CREATE INDEX IF NOT EXISTS idx_users_email_trgm ON users USING gin (email gin_trgm_ops);
+
+-- Append-only audit log of user mutations (written in the same transaction as the change)
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id TEXT NOT NULL,
+    action VARCHAR(20) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_id, created_at DESC);
+
 -- Create posts table
 CREATE TABLE IF NOT EXISTS posts (
-    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    id UUID PRIMARY KEY,
     user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
     title VARCHAR(500) NOT NULL,
     content TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
     updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- Optimistic concurrency version, incremented on every update
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
 -- Create indexes for posts table
 CREATE INDEX IF NOT EXISTS idx_posts_user_id ON posts(user_id);
 CREATE INDEX IF NOT EXISTS idx_posts_created_at ON posts(created_at DESC);
@@ -37,14 +77,73 @@ CREATE TABLE IF NOT EXISTS vocabulary (
     ja_word VARCHAR(200) NOT NULL,
     en_example TEXT,
     ja_example TEXT,
+    difficulty INTEGER NOT NULL DEFAULT 0,
+    favorite BOOLEAN NOT NULL DEFAULT FALSE,
+    review_count INTEGER NOT NULL DEFAULT 0,
+    ease_factor REAL NOT NULL DEFAULT 2.5,
+    next_review_at TIMESTAMPTZ,
+    last_reviewed_at TIMESTAMPTZ,
+    lang VARCHAR(8) NOT NULL DEFAULT 'ja',
+    tags TEXT[] NOT NULL DEFAULT '{}',
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
     updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
+-- Difficulty (0 = not assigned yet) for existing databases
+ALTER TABLE vocabulary ADD COLUMN IF NOT EXISTS difficulty INTEGER NOT NULL DEFAULT 0;
+
+-- Spaced-repetition review state (SM-2); entries that were never reviewed have no next_review_at
+ALTER TABLE vocabulary
+    ADD COLUMN IF NOT EXISTS review_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS ease_factor REAL NOT NULL DEFAULT 2.5,
+    ADD COLUMN IF NOT EXISTS next_review_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS last_reviewed_at TIMESTAMPTZ;
+
+-- Language of the translation (ISO 639-1); existing entries are Japanese
+ALTER TABLE vocabulary ADD COLUMN IF NOT EXISTS lang VARCHAR(8) NOT NULL DEFAULT 'ja';
+
+-- Free-form labels, stored lowercase and deduplicated
+ALTER TABLE vocabulary ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Starred entries (POST /api/vocabulary/:id/favorite)
+ALTER TABLE vocabulary ADD COLUMN IF NOT EXISTS favorite BOOLEAN NOT NULL DEFAULT FALSE;
+
 -- Create indexes for vocabulary table
+-- en_word is unique per language regardless of case (also the ON CONFLICT target for upserts)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_vocabulary_en_word_lang_unique ON vocabulary (LOWER(en_word), lang);
+-- The old index ignored lang and would reject the same word in a second language
+DROP INDEX IF EXISTS idx_vocabulary_en_word_lower_unique;
 CREATE INDEX IF NOT EXISTS idx_vocabulary_en_word ON vocabulary(en_word);
 CREATE INDEX IF NOT EXISTS idx_vocabulary_ja_word ON vocabulary(ja_word);
 CREATE INDEX IF NOT EXISTS idx_vocabulary_created_at ON vocabulary(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_vocabulary_next_review_at ON vocabulary(next_review_at);
+-- Incremental sync (GET /api/vocabulary/changes) scans by updated_at
+CREATE INDEX IF NOT EXISTS idx_vocabulary_updated_at ON vocabulary(updated_at);
+
+-- Create favorites join table
+CREATE TABLE IF NOT EXISTS favorites (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    vocabulary_id INTEGER NOT NULL REFERENCES vocabulary(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, vocabulary_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_favorites_vocabulary_id ON favorites(vocabulary_id);
+
+-- Create vocabulary history table: the values an entry had before each update (PATCH /api/vocabulary/:id)
+-- Only the newest 50 snapshots per entry are kept; older ones are deleted by the update that pushes them out
+CREATE TABLE IF NOT EXISTS vocabulary_history (
+    id BIGSERIAL PRIMARY KEY,
+    vocabulary_id INTEGER NOT NULL REFERENCES vocabulary(id) ON DELETE CASCADE,
+    en_word VARCHAR(200) NOT NULL,
+    ja_word VARCHAR(200) NOT NULL,
+    en_example TEXT,
+    ja_example TEXT,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vocabulary_history_vocabulary_id ON vocabulary_history(vocabulary_id, changed_at DESC);
 
 -- Display created tables
 SELECT 
@@ -0,0 +1,216 @@
+// Vocabulary read cache
+// In-process LRU cache in front of Database::get_vocabulary_by_id
+
+use lru::LruCache;
+use serde::Serialize;
+use std::{
+    future::Future,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::{error::ApiError, models::Vocabulary, notify::VocabularyChange};
+
+/// 語彙 ID をキーにした LRU キャッシュ。容量 0 なら何もしない (常に DB を読む)。
+/// 語彙は読み込みが多く更新が少ないため、ID 指定の取得だけをキャッシュする。
+pub struct VocabularyCache {
+    entries: Option<Mutex<LruCache<i32, Vocabulary>>>,
+    // Bumped on every invalidation so a load that raced with it doesn't store a stale row
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// キャッシュのヒット・ミス数と使用状況。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub capacity: usize,
+    pub len: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl VocabularyCache {
+    /// `capacity` 件まで保持するキャッシュを作る。0 なら無効。
+    pub fn new(capacity: usize) -> Self {
+        VocabularyCache {
+            entries: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// 容量が 1 以上で、実際にキャッシュするか。
+    pub fn is_enabled(&self) -> bool {
+        self.entries.is_some()
+    }
+
+    /// キャッシュにあればそれを返し、なければ `load` で読み込んで保存する。
+    /// `load` のエラー (NotFound を含む) はキャッシュせずにそのまま返す。
+    pub async fn get_or_load<F, Fut>(&self, id: i32, load: F) -> Result<Vocabulary, ApiError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vocabulary, ApiError>>,
+    {
+        let Some(entries) = &self.entries else {
+            return load().await;
+        };
+
+        if let Some(vocabulary) = lock(entries).get(&id) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(vocabulary.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let vocabulary = load().await?;
+
+        let mut entries = lock(entries);
+        if self.generation.load(Ordering::Acquire) == generation {
+            entries.put(id, vocabulary.clone());
+        }
+        Ok(vocabulary)
+    }
+
+    /// 指定した語彙をキャッシュから捨てる。更新・削除の後に呼ぶ。
+    pub fn invalidate(&self, id: i32) {
+        if let Some(entries) = &self.entries {
+            let mut entries = lock(entries);
+            self.generation.fetch_add(1, Ordering::AcqRel);
+            entries.pop(&id);
+        }
+    }
+
+    /// キャッシュを空にする。一括更新や、変更通知を取りこぼした可能性があるときに呼ぶ。
+    pub fn clear(&self) {
+        if let Some(entries) = &self.entries {
+            let mut entries = lock(entries);
+            self.generation.fetch_add(1, Ordering::AcqRel);
+            entries.clear();
+        }
+    }
+
+    /// 変更イベントに応じて該当する語彙、または全体を捨てる。
+    pub fn apply(&self, change: VocabularyChange) {
+        match change {
+            VocabularyChange::Entry(id) => self.invalidate(id),
+            VocabularyChange::All | VocabularyChange::Resync => self.clear(),
+        }
+    }
+
+    /// 現在のヒット・ミス数と使用状況。
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            capacity: self.entries.as_ref().map_or(0, |entries| lock(entries).cap().get()),
+            len: self.entries.as_ref().map_or(0, |entries| lock(entries).len()),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn lock(entries: &Mutex<LruCache<i32, Vocabulary>>) -> std::sync::MutexGuard<'_, LruCache<i32, Vocabulary>> {
+    entries.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReviewSchedule;
+    use chrono::Utc;
+    use std::sync::atomic::AtomicU32;
+
+    fn vocabulary(id: i32, ja_word: &str) -> Vocabulary {
+        Vocabulary {
+            id,
+            en_word: format!("word{}", id),
+            ja_word: ja_word.to_string(),
+            en_example: None,
+            ja_example: None,
+            lang: "ja".to_string(),
+            tags: Vec::new(),
+            difficulty: 0,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// `queries` を数えながら、`ja_word` を返す擬似的な DB 読み込み。
+    async fn fetch(queries: &AtomicU32, id: i32, ja_word: &str) -> Result<Vocabulary, ApiError> {
+        queries.fetch_add(1, Ordering::SeqCst);
+        Ok(vocabulary(id, ja_word))
+    }
+
+    #[tokio::test]
+    async fn test_second_fetch_is_served_from_cache() {
+        let cache = VocabularyCache::new(10);
+        let queries = AtomicU32::new(0);
+
+        let first = cache.get_or_load(1, || fetch(&queries, 1, "りんご")).await.unwrap();
+        let second = cache.get_or_load(1, || fetch(&queries, 1, "りんご")).await.unwrap();
+
+        assert_eq!(first.ja_word, second.ja_word);
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.stats(), CacheStats { capacity: 10, len: 1, hits: 1, misses: 1 });
+    }
+
+    #[tokio::test]
+    async fn test_update_busts_cached_entry() {
+        let cache = VocabularyCache::new(10);
+        let queries = AtomicU32::new(0);
+
+        cache.get_or_load(1, || fetch(&queries, 1, "りんご")).await.unwrap();
+        cache.apply(VocabularyChange::Entry(1));
+        let reloaded = cache.get_or_load(1, || fetch(&queries, 1, "林檎")).await.unwrap();
+
+        assert_eq!(reloaded.ja_word, "林檎");
+        assert_eq!(queries.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_racing_with_invalidation_is_not_cached() {
+        let cache = VocabularyCache::new(10);
+        let queries = AtomicU32::new(0);
+
+        // The row changes while it is being read; the stale result must not be kept
+        cache
+            .get_or_load(1, || async {
+                cache.invalidate(1);
+                fetch(&queries, 1, "stale").await
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(cache.stats().len, 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_cached() {
+        let cache = VocabularyCache::new(10);
+
+        let result = cache
+            .get_or_load(1, || async { Err(ApiError::not_found("Vocabulary entry with id 1")) })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(cache.stats().len, 0);
+    }
+
+    #[tokio::test]
+    async fn test_zero_capacity_disables_cache() {
+        let cache = VocabularyCache::new(0);
+        let queries = AtomicU32::new(0);
+
+        cache.get_or_load(1, || fetch(&queries, 1, "りんご")).await.unwrap();
+        cache.get_or_load(1, || fetch(&queries, 1, "りんご")).await.unwrap();
+
+        assert_eq!(queries.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.stats(), CacheStats { capacity: 0, len: 0, hits: 0, misses: 0 });
+    }
+}
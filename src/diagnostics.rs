@@ -0,0 +1,186 @@
+// Startup diagnostics
+// Backs the `--check` flag: validates config and the database without starting the server
+
+use std::{fmt, time::Instant};
+
+use crate::{
+    config::Config,
+    db::{with_startup_timeout, Database, SCHEMA_TABLES},
+};
+
+/// 診断項目 1 件の結果。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Ok,
+    /// 起動はできるが確認しておきたい項目。終了コードには影響しない
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Ok => " OK ",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        }
+    }
+}
+
+/// 診断項目 1 件。
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// `--check` が出力する診断レポート。`Fail` が 1 件でもあれば失敗 (終了コード 1) になる。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiagnosticReport {
+    pub checks: Vec<DiagnosticCheck>,
+}
+
+impl DiagnosticReport {
+    /// 空のレポートを作る。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 診断項目を追加する。
+    pub fn record(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(DiagnosticCheck { name, status, detail: detail.into() });
+    }
+
+    /// `Fail` の項目がないか。
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+
+    /// プロセスの終了コード。成功なら 0、失敗なら 1。
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() { 0 } else { 1 }
+    }
+
+    fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| check.status == CheckStatus::Fail).count()
+    }
+}
+
+impl fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "[{}] {}: {}", check.status.label(), check.name, check.detail)?;
+        }
+        match self.failures() {
+            0 => writeln!(f, "Startup check passed"),
+            failures => writeln!(f, "Startup check failed ({} check(s) failed)", failures),
+        }
+    }
+}
+
+/// 設定の読み込み・DB 接続・ヘルスチェック・スキーマの確認を順に行い、結果をレポートにまとめる。
+/// 途中で失敗した場合、それ以降の項目は確認できないのでそこで打ち切る。
+/// マイグレーションは実行せず、足りないテーブルを報告するだけ (起動時の `migrate` で作られる)。
+pub async fn run_startup_check() -> DiagnosticReport {
+    let mut report = DiagnosticReport::new();
+
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            report.record("configuration", CheckStatus::Fail, format!("{:#}", e));
+            return report;
+        }
+    };
+    report.record(
+        "configuration",
+        CheckStatus::Ok,
+        format!("environment {}, port {}", config.environment.as_str(), config.port),
+    );
+
+    let database_config = config.database.clone();
+    let pool_detail = format!(
+        "{} connections to {}:{}/{} ({})",
+        database_config.max_connections,
+        database_config.host,
+        database_config.port,
+        database_config.database,
+        if database_config.pooler { "through the pooler" } else { "direct" }
+    );
+    match database_config.pool_size_warning() {
+        Some(warning) => report.record("connection pool", CheckStatus::Warn, format!("{}; {}", pool_detail, warning)),
+        None => report.record("connection pool", CheckStatus::Ok, pool_detail),
+    }
+
+    let connection_timeout = database_config.connection_timeout;
+    let database = match Database::new(database_config).await {
+        Ok(database) => database,
+        Err(e) => {
+            report.record("database connection", CheckStatus::Fail, e.to_string());
+            return report;
+        }
+    };
+
+    let started = Instant::now();
+    match with_startup_timeout(connection_timeout, "Database health check", database.health_check()).await {
+        Ok(()) => report.record(
+            "database connection",
+            CheckStatus::Ok,
+            format!("SELECT 1 answered in {} ms", started.elapsed().as_millis()),
+        ),
+        Err(e) => {
+            report.record("database connection", CheckStatus::Fail, e.to_string());
+            database.close().await;
+            return report;
+        }
+    }
+
+    match with_startup_timeout(connection_timeout, "Schema check", database.missing_tables()).await {
+        Ok(missing) if missing.is_empty() => {
+            report.record("schema", CheckStatus::Ok, format!("all {} tables present", SCHEMA_TABLES.len()))
+        }
+        Ok(missing) => report.record(
+            "schema",
+            CheckStatus::Warn,
+            format!("missing tables {}; they are created by the migrations on startup", missing.join(", ")),
+        ),
+        Err(e) => report.record("schema", CheckStatus::Fail, e.to_string()),
+    }
+
+    database.close().await;
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings_do_not_fail_the_check() {
+        let mut report = DiagnosticReport::new();
+        report.record("configuration", CheckStatus::Ok, "environment production, port 8080");
+        report.record("schema", CheckStatus::Warn, "missing tables audit_log");
+
+        assert!(report.passed());
+        assert_eq!(report.exit_code(), 0);
+        assert_eq!(
+            report.to_string(),
+            "[ OK ] configuration: environment production, port 8080\n\
+             [WARN] schema: missing tables audit_log\n\
+             Startup check passed\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_check_sets_exit_code() {
+        let mut report = DiagnosticReport::new();
+        report.record("configuration", CheckStatus::Ok, "environment local, port 8080");
+        report.record("database connection", CheckStatus::Fail, "Database error: connection refused");
+
+        assert!(!report.passed());
+        assert_eq!(report.exit_code(), 1);
+        assert!(report.to_string().ends_with(
+            "[FAIL] database connection: Database error: connection refused\nStartup check failed (1 check(s) failed)\n"
+        ));
+    }
+}
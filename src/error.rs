@@ -1,11 +1,17 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
 use thiserror::Error;
 use tokio_postgres::error::SqlState;
+use utoipa::ToSchema;
+
+use crate::models::{
+    user::{EMAIL_UNIQUE_INDEX, LEGACY_EMAIL_UNIQUE_CONSTRAINT},
+    vocabulary::{DUPLICATE_WORD_MESSAGE, EN_WORD_UNIQUE_INDEX},
+};
 
 /// REST API 全体で共通利用するエラー型。
 /// `thiserror::Error` を derive することで `?` 演算子と相性の良い独自エラーを簡潔に書ける。
@@ -23,10 +29,46 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
     
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+    
+    #[error("Request timed out")]
+    Timeout,
+    
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+    
+    #[error("Request body is too large")]
+    PayloadTooLarge,
+    
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+    
     #[error("Internal server error")]
     Internal(#[from] anyhow::Error),
 }
 
+/// 503 を返すときに `Retry-After` で伝える、再試行までの秒数。
+/// プールの枯渇は数秒で解消することが多いので短めにしている。
+pub const RETRY_AFTER_SECONDS: u64 = 2;
+
+/// すべてのエラーレスポンスに共通する JSON の外枠 (`{"error": {"code", "message"}}`)。
+/// OpenAPI ドキュメントのスキーマとしても公開している。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+/// エラーレスポンスの中身。`code` は `VALIDATION_ERROR` や `NOT_FOUND` などの機械可読な識別子。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+}
+
 impl ApiError {
     /// バリデーションエラーを簡潔に生成するヘルパー。
     /// 型パラメータ `impl Into<String>` により、`&str`/`String` どちらも渡せる。
@@ -103,6 +145,56 @@ impl IntoResponse for ApiError {
                     message.clone(),
                 )
             }
+            ApiError::PreconditionFailed(ref message) => {
+                tracing::debug!("Conditional request precondition failed: {}", message);
+                (
+                    StatusCode::PRECONDITION_FAILED,
+                    "PRECONDITION_FAILED",
+                    message.clone(),
+                )
+            }
+            ApiError::MethodNotAllowed(ref message) => {
+                tracing::debug!("Rejected request with unsupported method: {}", message);
+                // The router adds the Allow header listing the methods the path does support
+                (
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "METHOD_NOT_ALLOWED",
+                    message.clone(),
+                )
+            }
+            ApiError::Timeout => {
+                tracing::warn!("Request exceeded the configured timeout");
+                (
+                    StatusCode::REQUEST_TIMEOUT,
+                    "TIMEOUT",
+                    "Request timed out".to_string(),
+                )
+            }
+            ApiError::UnsupportedMediaType(ref message) => {
+                tracing::debug!("Rejected request with unsupported content type: {}", message);
+                // Same envelope code as other request-shape problems; only the status differs
+                (
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "VALIDATION_ERROR",
+                    message.clone(),
+                )
+            }
+            ApiError::PayloadTooLarge => {
+                tracing::debug!("Rejected request body over the size limit");
+                (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "PAYLOAD_TOO_LARGE",
+                    "Request body is too large".to_string(),
+                )
+            }
+            ApiError::ServiceUnavailable(ref reason) => {
+                tracing::warn!("Service temporarily unavailable: {}", reason);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "SERVICE_UNAVAILABLE",
+                    "The service is overloaded, please retry shortly".to_string(),
+                )
+            }
             ApiError::Internal(ref err) => {
                 // Enhanced internal error logging with context
                 tracing::error!("Internal server error in PostgreSQL context: {}", err);
@@ -114,14 +206,18 @@ impl IntoResponse for ApiError {
             }
         };
 
-        let body = Json(json!({
-            "error": {
-                "code": error_code,
-                "message": message
-            }
-        }));
+        let body = Json(ErrorResponse {
+            error: ErrorBody {
+                code: error_code.to_string(),
+                message,
+            },
+        });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECONDS));
+        }
+        response
     }
 }
 
@@ -132,27 +228,15 @@ impl From<tokio_postgres::Error> for ApiError {
     fn from(err: tokio_postgres::Error) -> Self {
         match err.code() {
             Some(&SqlState::UNIQUE_VIOLATION) => {
-                // Check if it's an email constraint violation by examining the error message
-                let message = if err.to_string().contains("email") {
-                    "Email address already exists".to_string()
-                } else {
-                    "Resource already exists".to_string()
-                };
-                ApiError::Conflict(message)
+                let constraint = err.as_db_error().and_then(|db_error| db_error.constraint());
+                ApiError::Conflict(unique_violation_message(constraint).to_string())
             }
             Some(&SqlState::FOREIGN_KEY_VIOLATION) => {
                 ApiError::Validation("Referenced resource does not exist".to_string())
             }
             Some(&SqlState::NOT_NULL_VIOLATION) => {
-                // Extract column name from error message if possible
-                let message = if err.to_string().contains("name") {
-                    "Required field 'name' is missing".to_string()
-                } else if err.to_string().contains("email") {
-                    "Required field 'email' is missing".to_string()
-                } else {
-                    "Required field is missing".to_string()
-                };
-                ApiError::Validation(message)
+                let column = err.as_db_error().and_then(|db_error| db_error.column());
+                ApiError::Validation(not_null_violation_message(column, &err.to_string()))
             }
             Some(&SqlState::CHECK_VIOLATION) => {
                 ApiError::Validation("Data validation constraint violated".to_string())
@@ -163,8 +247,10 @@ impl From<tokio_postgres::Error> for ApiError {
             Some(&SqlState::NUMERIC_VALUE_OUT_OF_RANGE) => {
                 ApiError::Validation("Numeric value is out of range".to_string())
             }
-            Some(&SqlState::STRING_DATA_LENGTH_MISMATCH) => {
-                ApiError::Validation("Text data exceeds maximum length".to_string())
+            // VARCHAR(n) overflows are reported as right truncation; Postgres gives no column for them
+            Some(&SqlState::STRING_DATA_RIGHT_TRUNCATION) | Some(&SqlState::STRING_DATA_LENGTH_MISMATCH) => {
+                let message = err.as_db_error().map(|db_error| db_error.message().to_string()).unwrap_or_default();
+                ApiError::Validation(length_violation_message(&message))
             }
             Some(&SqlState::CONNECTION_EXCEPTION) | 
             Some(&SqlState::CONNECTION_DOES_NOT_EXIST) |
@@ -172,6 +258,11 @@ impl From<tokio_postgres::Error> for ApiError {
                 tracing::error!("PostgreSQL connection error: {}", err);
                 ApiError::Database("Database connection unavailable".to_string())
             }
+            Some(&SqlState::QUERY_CANCELED) => {
+                // Raised when DATABASE_STATEMENT_TIMEOUT_MS cancels a runaway statement
+                tracing::warn!("PostgreSQL statement canceled: {}", err);
+                ApiError::Timeout
+            }
             Some(&SqlState::INSUFFICIENT_PRIVILEGE) => {
                 tracing::error!("PostgreSQL privilege error: {}", err);
                 ApiError::Database("Database access denied".to_string())
@@ -191,8 +282,10 @@ impl From<deadpool_postgres::PoolError> for ApiError {
     fn from(err: deadpool_postgres::PoolError) -> Self {
         match err {
             deadpool_postgres::PoolError::Timeout(_) => {
+                // Either every pooled connection stayed busy past the wait timeout (overload) or opening
+                // a new connection took longer than connection_timeout; deadpool's message says which
                 tracing::warn!("Database connection pool timeout: {}", err);
-                ApiError::Database("Database connection timeout".to_string())
+                ApiError::ServiceUnavailable(format!("Database connection unavailable: {}", err))
             }
             deadpool_postgres::PoolError::Closed => {
                 tracing::error!("Database connection pool is closed: {}", err);
@@ -216,3 +309,124 @@ impl From<deadpool_postgres::PoolError> for ApiError {
 
 // Result type alias for convenience
 pub type ApiResult<T> = Result<T, ApiError>;
+
+/// 一意制約違反を利用者向けのメッセージにする。制約名だけで判別し、知らない制約は汎用のメッセージにする。
+/// エラー文面は値 (`Key (title)=(my email)` など) を含むので、推測には使わない。
+fn unique_violation_message(constraint: Option<&str>) -> &'static str {
+    match constraint {
+        Some(EN_WORD_UNIQUE_INDEX) => DUPLICATE_WORD_MESSAGE,
+        Some(EMAIL_UNIQUE_INDEX | LEGACY_EMAIL_UNIQUE_CONSTRAINT) => "Email address already exists",
+        _ => "Resource already exists",
+    }
+}
+
+/// NOT NULL 違反のメッセージで名前を出してよい列。API のフィールド名と一致するものだけに限り、内部の列名は漏らさない。
+const REQUIRED_FIELD_COLUMNS: &[&str] = &["name", "email", "en_word", "ja_word", "title", "user_id"];
+
+/// NOT NULL 違反を利用者向けのメッセージにする。列名は pg エラーの `column()` から取り、
+/// 取れない場合だけエラー文面から推測する。
+fn not_null_violation_message(column: Option<&str>, error_text: &str) -> String {
+    let field = column
+        .filter(|column| REQUIRED_FIELD_COLUMNS.contains(column))
+        .or_else(|| {
+            // Older servers or wrapped errors may not carry the column; look for `column "x"` in the text
+            REQUIRED_FIELD_COLUMNS
+                .iter()
+                .copied()
+                .find(|column| error_text.contains(&format!("column \"{}\"", column)))
+        });
+
+    match field {
+        Some(field) => format!("Required field '{}' is missing", field),
+        None => "Required field is missing".to_string(),
+    }
+}
+
+/// 文字数超過の pg エラー (`value too long for type character varying(255)`) を利用者向けのメッセージにする。
+/// 型の上限が読み取れればそれを含める。
+fn length_violation_message(error_text: &str) -> String {
+    let limit = error_text
+        .rsplit_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+        .and_then(|limit| limit.parse::<u32>().ok());
+
+    match limit {
+        Some(limit) => format!("Text data exceeds maximum length of {} characters", limit),
+        None => "Text data exceeds maximum length".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_null_violation_message_names_the_column() {
+        for column in ["name", "email", "en_word", "ja_word", "title", "user_id"] {
+            assert_eq!(
+                not_null_violation_message(Some(column), "null value violates not-null constraint"),
+                format!("Required field '{}' is missing", column)
+            );
+        }
+
+        // Without column(), fall back to the quoted column in the message rather than any substring
+        assert_eq!(
+            not_null_violation_message(None, r#"null value in column "ja_word" of relation "vocabulary""#),
+            "Required field 'ja_word' is missing"
+        );
+        assert_eq!(
+            not_null_violation_message(None, r#"null value in column "username" of relation "accounts""#),
+            "Required field is missing"
+        );
+        assert_eq!(
+            not_null_violation_message(Some("next_review_at"), "null value violates not-null constraint"),
+            "Required field is missing"
+        );
+    }
+
+    #[test]
+    fn test_length_violation_message_includes_limit() {
+        assert_eq!(
+            length_violation_message("value too long for type character varying(255)"),
+            "Text data exceeds maximum length of 255 characters"
+        );
+        assert_eq!(length_violation_message(""), "Text data exceeds maximum length");
+    }
+
+    #[test]
+    fn test_unique_violation_message() {
+        // "Apple" followed by "apple" trips the LOWER(en_word) index
+        assert_eq!(unique_violation_message(Some(EN_WORD_UNIQUE_INDEX)), DUPLICATE_WORD_MESSAGE);
+        assert_eq!(unique_violation_message(Some(EMAIL_UNIQUE_INDEX)), "Email address already exists");
+        assert_eq!(unique_violation_message(Some(LEGACY_EMAIL_UNIQUE_CONSTRAINT)), "Email address already exists");
+        // A constraint that merely mentions email, or none at all, gets the generic message
+        assert_eq!(unique_violation_message(Some("posts_email_digest_key")), "Resource already exists");
+        assert_eq!(unique_violation_message(None), "Resource already exists");
+    }
+
+    #[test]
+    fn test_service_unavailable_sets_retry_after() {
+        let response = ApiError::ServiceUnavailable("Database connection pool exhausted".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_connection_failure_stays_500() {
+        let response = ApiError::from(deadpool_postgres::PoolError::Closed).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+    }
+
+    #[test]
+    fn test_duplicate_word_is_conflict() {
+        let response = ApiError::Conflict(DUPLICATE_WORD_MESSAGE.to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_precondition_failed_is_412() {
+        let response = ApiError::PreconditionFailed("User 1 has been modified".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    }
+}
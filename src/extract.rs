@@ -0,0 +1,360 @@
+// Custom extractors
+// Wrappers around Axum extractors that reject with the standard ApiError envelope
+
+use axum::{
+    async_trait,
+    extract::{
+        rejection::{JsonRejection, PathRejection},
+        FromRef, FromRequest, FromRequestParts, Path, Query, Request,
+    },
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    error::ApiError,
+    models::{
+        pagination::{Pagination, PaginationParams},
+        Normalize,
+    },
+};
+
+/// `axum::extract::Path` の代わりに使うパスパラメータ抽出器。
+/// 標準の `Path` は変換失敗時にプレーンテキストの 400 を返すため、
+/// `ApiError::Validation` に読み替えて `{"error":{"code":"VALIDATION_ERROR",...}}` 形式に揃える。
+#[derive(Debug)]
+pub struct ApiPath<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(ApiPath(value)),
+            Err(rejection) => Err(path_rejection_to_api_error(rejection)),
+        }
+    }
+}
+
+/// `PathRejection` をクライアント向けのバリデーションエラーに変換する。
+/// ルーティング設定ミスなど、クライアントに原因がないものは 500 として扱う。
+fn path_rejection_to_api_error(rejection: PathRejection) -> ApiError {
+    match rejection {
+        PathRejection::FailedToDeserializePathParams(err) => {
+            ApiError::Validation(format!("Invalid path parameter: {}", err.body_text()))
+        }
+        other => ApiError::Internal(anyhow::anyhow!("Failed to extract path parameters: {}", other)),
+    }
+}
+
+/// クエリ文字列の `limit`/`offset` を読み、`Config::pagination` の既定値と上限を適用する。
+/// 一覧系ハンドラはこれを受け取るだけで、ページングの方針を個別に持たない。
+/// 数値でない値・1 未満の `limit`・負の `offset` は `ApiError::Validation` になる。
+#[async_trait]
+impl<S> FromRequestParts<S> for Pagination
+where
+    Arc<Config>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PaginationParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| ApiError::Validation(format!("Invalid query parameter: {}", rejection.body_text())))?;
+        let config = Arc::<Config>::from_ref(state);
+
+        params.resolve(&config.pagination).map_err(ApiError::Validation)
+    }
+}
+
+/// `axum::Json` の代わりに使うリクエストボディ抽出器。
+/// 標準の `Json` は JSON の構文エラーや型の不一致、`Content-Type` の不一致でプレーンテキストの
+/// 400/415/422 を返すため、`ApiError` に読み替えて `{"error":{"code":"VALIDATION_ERROR",...}}` 形式に揃える。
+/// 受け取った値には `Normalize::normalize` を適用してから渡すので、ハンドラが正規化を忘れることはない。
+#[derive(Debug)]
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned + Normalize,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(mut value)) => {
+                value.normalize();
+                Ok(ApiJson(value))
+            }
+            Err(rejection) => Err(json_rejection_to_api_error(rejection)),
+        }
+    }
+}
+
+/// `JsonRejection` をクライアント向けのエラーに変換する。
+/// 構文エラー・型の不一致は 400 (`Invalid JSON: missing field `email`` のような文言)、
+/// `Content-Type` の不一致は 415、ボディ上限超過は 413 にする。
+fn json_rejection_to_api_error(rejection: JsonRejection) -> ApiError {
+    match rejection {
+        JsonRejection::JsonDataError(err) => ApiError::Validation(invalid_json_message(&err)),
+        JsonRejection::JsonSyntaxError(err) => ApiError::Validation(invalid_json_message(&err)),
+        JsonRejection::MissingJsonContentType(_) => ApiError::UnsupportedMediaType(
+            "Expected request with `Content-Type: application/json`".to_string(),
+        ),
+        other if other.status() == StatusCode::PAYLOAD_TOO_LARGE => ApiError::PayloadTooLarge,
+        other => ApiError::Validation(format!("Failed to read request body: {}", other.body_text())),
+    }
+}
+
+/// Axum の `body_text` は「Failed to deserialize the JSON body into the target type: ...」のような前置きを含むので、
+/// serde が出した原因部分 (フィールドのパスと位置付き) だけを取り出す。
+fn invalid_json_message(err: &dyn std::error::Error) -> String {
+    let detail = err.source().map_or_else(|| err.to_string(), |source| source.to_string());
+    format!("Invalid JSON: {}", detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Method, Request, StatusCode},
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    async fn get_item(ApiPath(id): ApiPath<i32>) -> String {
+        id.to_string()
+    }
+
+    async fn get_user(ApiPath(id): ApiPath<Uuid>) -> String {
+        id.to_string()
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct NewItem {
+        name: String,
+        #[serde(default)]
+        quantity: u32,
+    }
+
+    impl Normalize for NewItem {
+        fn normalize(&mut self) {
+            crate::models::normalize::trim(&mut self.name);
+        }
+    }
+
+    async fn create_item(ApiJson(item): ApiJson<NewItem>) -> String {
+        format!("{} x{}", item.name, item.quantity)
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/items", post(create_item))
+            .route("/items/:id", get(get_item))
+            .route("/users/:id", get(get_user))
+    }
+
+    async fn post_item(content_type: Option<&str>) -> (StatusCode, Vec<u8>) {
+        post_item_body(content_type, r#"{"name":"apple"}"#).await
+    }
+
+    async fn post_item_body(content_type: Option<&str>, body: &'static str) -> (StatusCode, Vec<u8>) {
+        let mut request = Request::builder().method(Method::POST).uri("/items");
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        let response = app()
+            .oneshot(request.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    async fn send(uri: &str) -> (StatusCode, Vec<u8>) {
+        let response = app()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    #[tokio::test]
+    async fn test_valid_path_parameter_is_extracted() {
+        let (status, body) = send("/items/42").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"42");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_path_parameters_use_json_envelope() {
+        for uri in ["/items/abc", "/items/99999999999", "/users/not-a-uuid"] {
+            let (status, body) = send(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["error"]["code"], "VALIDATION_ERROR", "{}", uri);
+            assert!(json["error"]["message"].as_str().unwrap().starts_with("Invalid path parameter"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_body_is_extracted() {
+        let (status, body) = post_item(Some("application/json")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"apple x0");
+
+        let (status, _) = post_item(Some("application/json; charset=utf-8")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_json_body_is_normalized_before_the_handler() {
+        let (status, body) = post_item_body(Some("application/json"), r#"{"name":"  apple \n","quantity":2}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"apple x2");
+    }
+
+    #[tokio::test]
+    async fn test_request_types_arrive_normalized() {
+        use crate::models::{CreateUserRequest, UpdatePostRequest, UpdateVocabularyRequest};
+
+        async fn create_user(ApiJson(request): ApiJson<CreateUserRequest>) -> String {
+            format!("{}|{}", request.name, request.email)
+        }
+
+        async fn update_post(ApiJson(request): ApiJson<UpdatePostRequest>) -> String {
+            format!("{:?}|{:?}", request.title, request.content)
+        }
+
+        async fn update_vocabulary(ApiJson(request): ApiJson<UpdateVocabularyRequest>) -> String {
+            format!("{:?}|{:?}|{:?}", request.en_word, request.en_example, request.tags)
+        }
+
+        let app = Router::new()
+            .route("/users", post(create_user))
+            .route("/posts", post(update_post))
+            .route("/vocabulary", post(update_vocabulary));
+        let cases = [
+            ("/users", r#"{"name":"  Alice ","email":" Alice@Example.COM "}"#, "Alice|alice@example.com"),
+            // An empty content still means "clear the content" after trimming
+            ("/posts", r#"{"title":" Hello ","content":"   ","expected_version":1}"#, r#"Some("Hello")|Some("")"#),
+            (
+                "/vocabulary",
+                r#"{"en_word":" ice   cream ","en_example":"  ","tags":[" Food "]}"#,
+                r#"Some("ice cream")|Some(None)|Some(["food"])"#,
+            ),
+        ];
+
+        for (uri, body, expected) in cases {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(String::from_utf8(body.to_vec()).unwrap(), expected, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_wrong_content_type_uses_json_envelope() {
+        for content_type in [None, Some("text/plain"), Some("application/x-www-form-urlencoded")] {
+            let (status, body) = post_item(content_type).await;
+            assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{:?}", content_type);
+
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["error"]["code"], "VALIDATION_ERROR", "{:?}", content_type);
+            assert!(json["error"]["message"].as_str().unwrap().contains("application/json"));
+        }
+    }
+
+    async fn invalid_json_message_for(body: &'static str) -> String {
+        let (status, body) = post_item_body(Some("application/json"), body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "VALIDATION_ERROR");
+        json["error"]["message"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_missing_field_is_reported() {
+        let message = invalid_json_message_for(r#"{"quantity":1}"#).await;
+        assert!(message.starts_with("Invalid JSON: missing field `name`"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_type_mismatch_names_the_field() {
+        let message = invalid_json_message_for(r#"{"name":"apple","quantity":"three"}"#).await;
+        assert!(message.starts_with("Invalid JSON: quantity: invalid type: string"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_fields_in_create_requests_are_rejected() {
+        use crate::models::{CreatePostRequest, CreateUserRequest, CreateVocabularyRequest};
+
+        async fn accept<T>(ApiJson(_): ApiJson<T>) -> &'static str {
+            "ok"
+        }
+
+        let app = Router::new()
+            .route("/users", post(accept::<CreateUserRequest>))
+            .route("/posts", post(accept::<CreatePostRequest>))
+            .route("/vocabulary", post(accept::<CreateVocabularyRequest>));
+        let user_id = Uuid::nil();
+        let cases = [
+            ("/users", r#"{"name":"Alice","email":"alice@example.com"}"#.to_string(), "role"),
+            ("/posts", format!(r#"{{"user_id":"{}","title":"Hello"}}"#, user_id), "body"),
+            ("/vocabulary", r#"{"en_word":"apple","ja_word":"りんご"}"#.to_string(), "meaning"),
+        ];
+
+        for (uri, valid, extra) in cases {
+            let with_extra = format!(r#"{},"{}":"x"}}"#, valid.trim_end_matches('}'), extra);
+            for (body, expected) in [(valid, StatusCode::OK), (with_extra, StatusCode::BAD_REQUEST)] {
+                let request = Request::builder()
+                    .method(Method::POST)
+                    .uri(uri)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
+                    .unwrap();
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), expected, "{}", uri);
+
+                if expected == StatusCode::BAD_REQUEST {
+                    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    assert_eq!(json["error"]["code"], "VALIDATION_ERROR");
+                    let message = json["error"]["message"].as_str().unwrap();
+                    assert!(message.starts_with(&format!("Invalid JSON: {}: unknown field `{}`", extra, extra)), "{}", message);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_is_reported() {
+        let message = invalid_json_message_for(r#"{"name":"#).await;
+        assert!(message.starts_with("Invalid JSON: "), "{}", message);
+    }
+}
@@ -0,0 +1,107 @@
+// Admin handlers
+// HTTP handlers for maintenance operations on curated data
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::{
+    config::Config,
+    db::Database,
+    error::{ApiError, ErrorResponse},
+    models::{
+        audit::{AuditEntry, AuditLogParams},
+        pagination::{Pagination, PaginationParams},
+        vocabulary::load_seed_entries,
+    },
+};
+
+/// 難易度の一括割り当ての結果。`updated` は難易度を設定した語彙の件数。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AutoDifficultyResponse {
+    pub updated: u64,
+}
+
+/// 語彙のリセット結果。`seeded` は入れ直したシードデータの件数。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResetVocabularyResponse {
+    pub seeded: u64,
+}
+
+/// `POST /api/admin/vocabulary/auto-difficulty`
+/// 難易度が未設定の語彙に、英単語の長さに基づく難易度を一括で割り当てる。
+/// しきい値は `DIFFICULTY_LENGTH_THRESHOLDS` で変更でき、レスポンスは `{ "updated": n }`。
+#[utoipa::path(
+    post,
+    path = "/api/admin/vocabulary/auto-difficulty",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Difficulty assigned to unrated entries", body = AutoDifficultyResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+pub async fn auto_assign_vocabulary_difficulty(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Auto-assigning vocabulary difficulty with {:?}", config.difficulty_heuristic);
+    
+    let updated = db.auto_assign_vocabulary_difficulty(&config.difficulty_heuristic).await?;
+    
+    Ok((StatusCode::OK, Json(AutoDifficultyResponse { updated })))
+}
+
+/// `POST /api/admin/vocabulary/reset`
+/// 語彙テーブルを空にして ID の採番を戻し、シードデータ (`VOCABULARY_SEED_FILE` または組み込みの 5 語) を入れ直す。
+/// ローカル環境でのみルーターに登録され、本番ではルート自体が存在しない (404)。レスポンスは `{ "seeded": n }`。
+#[utoipa::path(
+    post,
+    path = "/api/admin/vocabulary/reset",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Vocabulary wiped and re-seeded (local environment only)", body = ResetVocabularyResponse),
+        (status = 500, description = "Seed file could not be loaded or database error", body = ErrorResponse),
+    )
+)]
+pub async fn reset_vocabulary(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let entries = load_seed_entries(config.vocabulary_seed_file.as_deref(), config.seed_vocabulary_count)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Failed to load vocabulary seed data: {}", e)))?;
+    
+    let seeded = db.reset_vocabulary(&entries).await?;
+    
+    Ok((StatusCode::OK, Json(ResetVocabularyResponse { seeded })))
+}
+
+/// `GET /api/admin/audit?entity_id=<id>&limit=<n>&offset=<m>`
+/// ユーザーの作成・更新・削除の監査ログを新しい順に返す。`entity_id` で対象を絞れる。
+/// 認証がまだないため、ローカル環境でのみルーターに登録する。
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit",
+    tag = "admin",
+    params(AuditLogParams, PaginationParams),
+    responses(
+        (status = 200, description = "Audit entries, newest first (local environment only)", body = Vec<AuditEntry>),
+        (status = 400, description = "Invalid limit or offset", body = ErrorResponse),
+    )
+)]
+pub async fn get_audit_log(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<AuditLogParams>,
+    pagination: Pagination,
+) -> Result<impl IntoResponse, ApiError> {
+    let entries = db.get_audit_log(params.entity_id(), pagination.limit, pagination.offset).await?;
+
+    info!("Retrieved {} audit log entries", entries.len());
+    Ok((StatusCode::OK, Json(entries)))
+}
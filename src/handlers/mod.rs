@@ -1,15 +1,633 @@
 // Handlers module
 // HTTP handlers for the REST API
 
+pub mod admin;
 pub mod users;
 pub mod posts;
 pub mod vocabulary;
+pub mod search;
 
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    response::{sse::Event, IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, Stream, StreamExt};
+use serde::Serialize;
+use std::{collections::BTreeMap, convert::Infallible, future::Future, sync::Arc, time::Duration};
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::ToSchema;
+
+use crate::{
+    config::{Config, EmptyListStatus},
+    db::{Database, HealthReport},
+    error::ApiError,
+    models::{
+        fields::{IdFormat, WithIdFormat},
+        pagination::{PageInfo, Paginated},
+    },
+    state::AppState,
+};
+
+/// 要求された件数が上限に丸められたことを示すレスポンスヘッダー。
+/// 400 で弾く代わりに黙って丸めると気付きにくいため、クライアントが検知できるようにしている。
+pub const PAGE_SIZE_CLAMPED_HEADER: HeaderName = HeaderName::from_static("x-page-size-clamped");
+
+/// ページングした一覧で、絞り込み条件に合う全件数を返すレスポンスヘッダー。
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// クイズのセッションを識別するリクエストヘッダー。
+/// 付与されている場合、ランダム出題で同じセッションに直近出した語彙を避ける。
+pub const SESSION_ID_HEADER: HeaderName = HeaderName::from_static("x-session-id");
+
+/// `X-Session-Id` の最大長。これを超える値はセッションなしとして扱う。
+const MAX_SESSION_ID_LEN: usize = 128;
+
+/// リクエストヘッダーからセッション ID を取り出す。空・長すぎる・非 ASCII の値は無視する。
+pub fn session_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(SESSION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_SESSION_ID_LEN)
+        .map(str::to_string)
+}
+
+/// `If-Unmodified-Since` ヘッダーを HTTP 日付 (`Sun, 06 Nov 1994 08:49:37 GMT`) として読む。
+/// RFC 9110 に従い、日付として解釈できない値はヘッダーがないものとして無視する。
+pub fn if_unmodified_since_from_headers(headers: &HeaderMap) -> Option<DateTime<Utc>> {
+    headers
+        .get(header::IF_UNMODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value.trim()).ok())
+        .map(|since| since.with_timezone(&Utc))
+}
+
+/// どのルートにも一致しなかったリクエストへの 404。axum 既定の空のレスポンスではなく、共通の JSON エンベロープで返す。
+pub async fn route_not_found(method: Method, uri: Uri) -> ApiError {
+    ApiError::not_found(format!("Route {} {}", method, uri.path()))
+}
+
+/// パスはあるがメソッドが違うリクエストへの 405。許可されたメソッドの `Allow` ヘッダーはルーターが付ける。
+pub async fn method_not_allowed(method: Method, uri: Uri) -> ApiError {
+    ApiError::MethodNotAllowed(format!("Method {} is not allowed for {}", method, uri.path()))
+}
+
+/// 件数が丸められた場合に `X-Page-Size-Clamped: true` を含むヘッダーを返す。
+/// `enabled` は `Config::page_size_clamp_header` を渡す想定。
+pub fn page_size_clamped_headers(clamped: bool, enabled: bool) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if clamped && enabled {
+        headers.insert(PAGE_SIZE_CLAMPED_HEADER, HeaderValue::from_static("true"));
+    }
+    headers
+}
+
+/// 変更の少ない読み取り系レスポンスに付ける `Cache-Control: public, max-age=<秒>`。
+/// `max_age` (`VOCAB_CACHE_MAX_AGE`) が `None` なら何も付けない。書き込み系のレスポンスには使わないこと。
+pub fn cache_control_headers(max_age: Option<Duration>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(max_age) = max_age {
+        let value = format!("public, max-age={}", max_age.as_secs());
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_str(&value).expect("digits are a valid header value"));
+    }
+    headers
+}
+
+/// ページングした一覧用に、`X-Total-Count` と (丸めた場合は) `X-Page-Size-Clamped` を返す。
+pub fn paginated_headers(total: i64, clamped: bool, clamp_header_enabled: bool) -> HeaderMap {
+    let mut headers = page_size_clamped_headers(clamped, clamp_header_enabled);
+    headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+    headers
+}
+
+/// 一覧の本文を作る。`page` があれば `{"data": [...], "page": {...}}` のエンベロープで、
+/// なければ従来どおり配列そのままで返す (`?envelope=true` はオプトイン)。
+pub fn list_response<T: Serialize>(items: Vec<T>, page: Option<PageInfo>) -> Response {
+    match page {
+        Some(page) => Json(Paginated { data: items, page }).into_response(),
+        None => Json(items).into_response(),
+    }
+}
+
+/// 要素のストリームを、JSON 配列として少しずつ書き出す本文にする。全体をメモリに溜めないので巨大な一覧向け。
+/// 出力は同じ要素を `Json(Vec<T>)` で返したときと同じバイト列になる。
+/// 途中でエラーになった場合はステータスを変えられないため、本文を打ち切って (不完全な JSON のまま) 接続を閉じる。
+pub fn json_array_body<S, T>(items: S) -> Body
+where
+    S: Stream<Item = Result<T, ApiError>> + Send + 'static,
+    T: Serialize,
+{
+    let mut first = true;
+    let elements = items.map(move |item| {
+        let mut chunk = if first { Vec::new() } else { vec![b','] };
+        first = false;
+        serde_json::to_writer(&mut chunk, &item?).map_err(|e| ApiError::Internal(e.into()))?;
+        Ok::<_, ApiError>(Bytes::from(chunk))
+    });
+    let array = stream::once(async { Ok(Bytes::from_static(b"[")) })
+        .chain(elements)
+        .chain(stream::once(async { Ok(Bytes::from_static(b"]")) }))
+        .inspect(|chunk| {
+            if let Err(e) = chunk {
+                tracing::error!("Aborting streamed JSON response: {}", e);
+            }
+        });
+    Body::from_stream(array)
+}
+
+/// `broadcast` の受信機を SSE のイベント列にする。各値を `id_format` の JSON にして 1 つの `data:` イベントで送る。
+/// 遅れて `Lagged` になった場合は切断せず、読み飛ばした件数をコメント行で知らせて続ける。
+/// チャンネルが閉じるか `shutdown` が完了したら終わる。クライアントが切断するとストリームごと受信機が破棄される。
+pub fn broadcast_events<T>(
+    receiver: broadcast::Receiver<T>,
+    id_format: IdFormat,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> impl Stream<Item = Result<Event, Infallible>>
+where
+    T: Serialize + Clone + Send + 'static,
+{
+    stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(item) => match Event::default().json_data(WithIdFormat(&item, id_format)) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::error!("Skipping event that failed to serialize: {}", e);
+                        continue;
+                    }
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event stream subscriber fell behind; skipped {} events", skipped);
+                    Event::default().comment(format!("skipped {} events", skipped))
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), receiver));
+        }
+    })
+    .take_until(shutdown)
+}
+
+/// `json_array_body` の本文に `Content-Type: application/json` を付けたレスポンス。
+pub fn json_array_response(headers: HeaderMap, body: Body) -> Response {
+    (StatusCode::OK, [(header::CONTENT_TYPE, "application/json")], headers, body).into_response()
+}
+
+/// 一覧の結果が空で、設定が `EmptyListStatus::NotFound` の場合に 404 を返す。
+/// `resource` は「No posts found」のようにメッセージへ埋め込まれる複数形の名前。
+pub fn check_empty_list<T>(items: &[T], status: EmptyListStatus, resource: &str) -> Result<(), ApiError> {
+    if items.is_empty() && status == EmptyListStatus::NotFound {
+        return Err(ApiError::NotFound(format!("No {} found", resource)));
+    }
+    Ok(())
+}
+
+/// `GET /health` のレスポンス。どのリビジョンが動いているかをプローブのついでに確認できる。
+/// `version`・`commit` は `/info` と同じく `INFO_ENDPOINT_ENABLED=false` のときは出さない。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthStatus {
+    pub status: &'static str,
+    pub environment: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<&'static str>,
+}
+
+impl HealthStatus {
+    /// 稼働中を表すレスポンスを作る。`expose_build` が `false` ならビルド情報を省く。
+    pub fn ok(environment: &'static str, expose_build: bool) -> Self {
+        HealthStatus {
+            status: "ok",
+            environment,
+            version: expose_build.then_some(env!("CARGO_PKG_VERSION")),
+            commit: expose_build.then_some(env!("GIT_SHA")),
+        }
+    }
+}
 
 /// ヘルスチェック用ハンドラ。
-/// 200 OK と短いメッセージを返すだけだが、監視ツールや Cloud Run の
+/// DB には触れずに 200 OK を返すだけなので、監視ツールや Cloud Run の
 /// ヘルスプローブにそのまま利用できる。
-pub async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, "Hello Rust, Axum and Neon! 🚀")
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "system",
+    responses((status = 200, description = "Service is up", body = HealthStatus))
+)]
+pub async fn health_check(State(config): State<Arc<Config>>) -> impl IntoResponse {
+    let status = HealthStatus::ok(config.environment.as_str(), config.info_endpoint_enabled);
+    (StatusCode::OK, Json(status))
+}
+
+/// `GET /health/ready` のレスポンス。依存先ごとの結果と、それらをまとめた `healthy`。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    /// すべての依存先が正常か
+    pub healthy: bool,
+    /// 依存先ごとの結果。`primary` と、`DATABASE_READ_URL` があれば `read_replica`
+    pub components: BTreeMap<String, HealthReport>,
+}
+
+impl ReadinessReport {
+    pub fn new(components: BTreeMap<String, HealthReport>) -> Self {
+        ReadinessReport {
+            healthy: components.values().all(|component| component.healthy),
+            components,
+        }
+    }
+}
+
+/// `GET /health/ready`
+/// 設定されている依存先 (DB のプライマリ、あればリードレプリカ) をまとめて確認し、それぞれの往復時間と
+/// 接続プールの状態を JSON で返す。1 つでも落ちていれば 503 を返すので、レディネスプローブと
+/// プール枯渇の調査の両方に使える。
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "system",
+    responses(
+        (status = 200, description = "Every dependency is reachable", body = ReadinessReport),
+        (status = 503, description = "At least one dependency is unreachable", body = ReadinessReport),
+    )
+)]
+pub async fn readiness_check(State(db): State<Arc<Database>>) -> impl IntoResponse {
+    let report = ReadinessReport::new(db.check_all().await);
+    (readiness_status(&report), Json(report))
+}
+
+/// レポートに応じたステータスコード。
+fn readiness_status(report: &ReadinessReport) -> StatusCode {
+    if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// `GET /info` のレスポンス。デプロイとインシデントの突き合わせに使う。
+/// `git_sha`・`build_time`・`rust_version` は `build.rs` がビルド時に埋め込む。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub build_time: &'static str,
+    pub rust_version: &'static str,
+    pub environment: &'static str,
+    pub uptime_seconds: u64,
+}
+
+impl BuildInfo {
+    /// ビルド時の情報に、実行環境と稼働時間を組み合わせる。
+    pub fn new(environment: &'static str, uptime_seconds: u64) -> Self {
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("GIT_SHA"),
+            build_time: env!("BUILD_TIME"),
+            rust_version: env!("RUST_VERSION"),
+            environment,
+            uptime_seconds,
+        }
+    }
+}
+
+/// `GET /info`
+/// バージョン・コミット・ビルド日時・稼働時間を返す。`INFO_ENDPOINT_ENABLED=false` なら公開しない。
+#[utoipa::path(
+    get,
+    path = "/info",
+    tag = "system",
+    responses(
+        (status = 200, description = "Build and runtime information", body = BuildInfo),
+        (status = 404, description = "Disabled with INFO_ENDPOINT_ENABLED=false"),
+    )
+)]
+pub async fn build_info(State(state): State<AppState>) -> impl IntoResponse {
+    let info = BuildInfo::new(
+        state.config.environment.as_str(),
+        state.started_at.elapsed().as_secs(),
+    );
+    (StatusCode::OK, Json(info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{CircuitState, CircuitStatus, PoolStats},
+        models::Vocabulary,
+    };
+    use axum::Json;
+
+    async fn send_to_fallback_router(method: Method, uri: &str) -> Response {
+        use axum::routing::get;
+        use tower::ServiceExt;
+
+        axum::Router::new()
+            .route("/items/:id", get(|| async { "item" }).patch(|| async { "updated" }))
+            .fallback(route_not_found)
+            .method_not_allowed_fallback(method_not_allowed)
+            .oneshot(axum::http::Request::builder().method(method).uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn error_code(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        json["error"]["code"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_method_gets_json_405_with_allow_header() {
+        let response = send_to_fallback_router(Method::POST, "/items/1").await;
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let allow = response.headers().get(header::ALLOW).unwrap().to_str().unwrap().to_string();
+        let allowed: Vec<&str> = allow.split(',').map(str::trim).collect();
+        assert!(allowed.contains(&"GET") && allowed.contains(&"PATCH"), "Allow: {}", allow);
+        assert!(!allowed.contains(&"POST"), "Allow: {}", allow);
+        assert_eq!(error_code(response).await, "METHOD_NOT_ALLOWED");
+
+        // Supported methods are unaffected
+        assert_eq!(send_to_fallback_router(Method::PATCH, "/items/1").await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_path_gets_json_404() {
+        let response = send_to_fallback_router(Method::GET, "/nothing-here").await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(error_code(response).await, "NOT_FOUND");
+    }
+
+    fn vocabulary(id: i32, en_word: &str) -> Vocabulary {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "en_word": en_word,
+            "ja_word": "語",
+            "en_example": null,
+            "ja_example": null,
+            "tags": ["food"],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-02T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_streamed_json_array_matches_buffered_output() {
+        for items in [Vec::new(), vec![vocabulary(1, "apple")], vec![vocabulary(1, "apple"), vocabulary(2, "book"), vocabulary(3, "cat")]] {
+            let buffered = Json(items.clone()).into_response();
+            let buffered = axum::body::to_bytes(buffered.into_body(), usize::MAX).await.unwrap();
+
+            let streamed = json_array_response(HeaderMap::new(), json_array_body(stream::iter(items.into_iter().map(Ok))));
+            assert_eq!(streamed.headers().get(header::CONTENT_TYPE).unwrap(), "application/json");
+            let streamed = axum::body::to_bytes(streamed.into_body(), usize::MAX).await.unwrap();
+
+            assert_eq!(streamed, buffered);
+            assert!(serde_json::from_slice::<Vec<Vocabulary>>(&streamed).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_json_array_is_cut_short_on_error() {
+        let items = stream::iter(vec![Ok(vocabulary(1, "apple")), Err(ApiError::Database("connection lost".to_string()))]);
+        let body = json_array_body(items);
+
+        assert!(axum::body::to_bytes(body, usize::MAX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_events_reports_lag_and_ends_when_closed() {
+        let (sender, receiver) = broadcast::channel(1);
+        sender.send(vocabulary(1, "apple")).unwrap();
+        sender.send(vocabulary(2, "book")).unwrap();
+        drop(sender);
+
+        let response = axum::response::Sse::new(broadcast_events(receiver, IdFormat::Number, std::future::pending())).into_response();
+        assert_eq!(response.headers().get(header::CONTENT_TYPE).unwrap(), "text/event-stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let expected_data = serde_json::to_string(&vocabulary(2, "book")).unwrap();
+        assert_eq!(body, format!(": skipped 1 events\n\ndata: {}\n\n", expected_data));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_events_use_the_requested_id_format() {
+        let (sender, receiver) = broadcast::channel(1);
+        sender.send(vocabulary(1, "apple")).unwrap();
+        drop(sender);
+
+        let response = axum::response::Sse::new(broadcast_events(receiver, IdFormat::String, std::future::pending())).into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let data: serde_json::Value = serde_json::from_str(body.trim().strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(data["id"], serde_json::json!("1"));
+        assert_eq!(data["en_word"], "apple");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_json_array_uses_the_requested_id_format() {
+        let items = stream::iter(vec![Ok(WithIdFormat(vocabulary(1, "apple"), IdFormat::String))]);
+        let body = axum::body::to_bytes(json_array_body(items), usize::MAX).await.unwrap();
+
+        let streamed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(streamed[0]["id"], serde_json::json!("1"));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_events_end_when_shutdown_starts() {
+        let (sender, receiver) = broadcast::channel::<Vocabulary>(4);
+        let shutdown = crate::state::Shutdown::new();
+
+        let response = axum::response::Sse::new(broadcast_events(receiver, IdFormat::Number, shutdown.started())).into_response();
+        let body = tokio::spawn(axum::body::to_bytes(response.into_body(), usize::MAX));
+        shutdown.trigger();
+
+        // The channel is still open; only the shutdown notice can end the stream
+        let body = tokio::time::timeout(Duration::from_secs(2), body)
+            .await
+            .expect("event stream kept running after shutdown began")
+            .unwrap()
+            .unwrap();
+        assert!(body.is_empty());
+        assert_eq!(sender.receiver_count(), 0);
+    }
+
+    #[test]
+    fn test_clamped_request_sets_header() {
+        let response = (
+            StatusCode::OK,
+            page_size_clamped_headers(true, true),
+            Json(vec![1, 2, 3]),
+        )
+            .into_response();
+
+        assert_eq!(response.headers().get(PAGE_SIZE_CLAMPED_HEADER).unwrap(), "true");
+    }
+
+    #[test]
+    fn test_cache_control_header_uses_configured_max_age() {
+        let headers = cache_control_headers(Some(Duration::from_secs(60)));
+        assert_eq!(headers.get(header::CACHE_CONTROL).unwrap(), "public, max-age=60");
+
+        assert!(cache_control_headers(None).is_empty());
+    }
+
+    #[test]
+    fn test_unclamped_or_disabled_request_omits_header() {
+        assert!(page_size_clamped_headers(false, true).is_empty());
+        assert!(page_size_clamped_headers(true, false).is_empty());
+    }
+
+    #[test]
+    fn test_paginated_headers_include_total_count() {
+        let headers = paginated_headers(45, false, true);
+        assert_eq!(headers.get(TOTAL_COUNT_HEADER).unwrap(), "45");
+        assert!(headers.get(PAGE_SIZE_CLAMPED_HEADER).is_none());
+
+        let clamped = paginated_headers(0, true, true);
+        assert_eq!(clamped.get(TOTAL_COUNT_HEADER).unwrap(), "0");
+        assert_eq!(clamped.get(PAGE_SIZE_CLAMPED_HEADER).unwrap(), "true");
+    }
+
+    #[tokio::test]
+    async fn test_empty_vocabulary_list_returns_ok_by_default() {
+        let vocabulary_list: Vec<Vocabulary> = Vec::new();
+        assert!(check_empty_list(&vocabulary_list, EmptyListStatus::Ok, "vocabulary entries").is_ok());
+
+        let response = (StatusCode::OK, Json(vocabulary_list)).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"[]");
+    }
+
+    #[test]
+    fn test_empty_vocabulary_list_returns_not_found_when_configured() {
+        let vocabulary_list: Vec<Vocabulary> = Vec::new();
+        let err = check_empty_list(&vocabulary_list, EmptyListStatus::NotFound, "vocabulary entries")
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Not found: No vocabulary entries found");
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_build_info_fields_are_present() {
+        let json = serde_json::to_value(BuildInfo::new("local", 42)).unwrap();
+
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["environment"], "local");
+        assert_eq!(json["uptime_seconds"], 42);
+        for field in ["git_sha", "build_time", "rust_version"] {
+            assert!(!json[field].as_str().unwrap().is_empty(), "{} should be set", field);
+        }
+    }
+
+    #[test]
+    fn test_health_status_hides_build_details_when_info_is_disabled() {
+        let json = serde_json::to_value(HealthStatus::ok("production", true)).unwrap();
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["environment"], "production");
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["commit"], env!("GIT_SHA"));
+
+        let json = serde_json::to_value(HealthStatus::ok("production", false)).unwrap();
+        assert_eq!(json, serde_json::json!({ "status": "ok", "environment": "production" }));
+    }
+
+    #[test]
+    fn test_readiness_report_shape_and_status() {
+        let pool = PoolStats { max_size: 10, size: 3, available: 2, waiting: 0 };
+        let circuit = CircuitStatus { state: CircuitState::Closed, consecutive_failures: 0 };
+        let healthy = HealthReport { healthy: true, latency_ms: Some(1.5), error: None, pool, circuit };
+        let report = ReadinessReport::new(BTreeMap::from([("primary".to_string(), healthy)]));
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["healthy"], true);
+        assert_eq!(json["components"]["primary"]["latency_ms"], 1.5);
+        assert_eq!(json["components"]["primary"]["pool"]["size"], 3);
+        assert_eq!(json["components"]["primary"]["pool"]["available"], 2);
+        assert_eq!(json["components"]["primary"]["pool"]["waiting"], 0);
+        assert_eq!(json["components"]["primary"]["circuit"]["state"], "closed");
+        assert_eq!(readiness_status(&report), StatusCode::OK);
+
+        let unreachable = HealthReport {
+            healthy: false,
+            latency_ms: None,
+            error: Some("Database connection timeout".to_string()),
+            pool,
+            circuit: CircuitStatus { state: CircuitState::Open, consecutive_failures: 5 },
+        };
+        let report = ReadinessReport::new(BTreeMap::from([("primary".to_string(), unreachable)]));
+        assert_eq!(readiness_status(&report), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_one_component_down_fails_readiness_but_reports_each() {
+        let pool = PoolStats { max_size: 10, size: 1, available: 1, waiting: 0 };
+        let circuit = CircuitStatus { state: CircuitState::Closed, consecutive_failures: 0 };
+        let primary = HealthReport { healthy: true, latency_ms: Some(0.8), error: None, pool, circuit };
+        let read_replica = HealthReport {
+            healthy: false,
+            latency_ms: None,
+            error: Some("Database connection timeout".to_string()),
+            pool,
+            circuit,
+        };
+        let report = ReadinessReport::new(BTreeMap::from([
+            ("primary".to_string(), primary),
+            ("read_replica".to_string(), read_replica),
+        ]));
+
+        assert!(!report.healthy);
+        assert_eq!(readiness_status(&report), StatusCode::SERVICE_UNAVAILABLE);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["components"]["primary"]["healthy"], true);
+        assert_eq!(json["components"]["read_replica"]["healthy"], false);
+        assert_eq!(json["components"]["read_replica"]["error"], "Database connection timeout");
+    }
+
+    #[test]
+    fn test_session_id_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(session_id_from_headers(&headers), None);
+
+        headers.insert(SESSION_ID_HEADER, HeaderValue::from_static(" quiz-1 "));
+        assert_eq!(session_id_from_headers(&headers), Some("quiz-1".to_string()));
+
+        headers.insert(SESSION_ID_HEADER, HeaderValue::from_static("   "));
+        assert_eq!(session_id_from_headers(&headers), None);
+
+        let too_long = HeaderValue::from_str(&"a".repeat(MAX_SESSION_ID_LEN + 1)).unwrap();
+        headers.insert(SESSION_ID_HEADER, too_long);
+        assert_eq!(session_id_from_headers(&headers), None);
+    }
+
+    #[test]
+    fn test_if_unmodified_since_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(if_unmodified_since_from_headers(&headers), None);
+
+        headers.insert(header::IF_UNMODIFIED_SINCE, HeaderValue::from_static("Wed, 01 May 2024 12:00:00 GMT"));
+        assert_eq!(
+            if_unmodified_since_from_headers(&headers).map(|since| since.to_rfc3339()),
+            Some("2024-05-01T12:00:00+00:00".to_string())
+        );
+
+        headers.insert(header::IF_UNMODIFIED_SINCE, HeaderValue::from_static("yesterday"));
+        assert_eq!(if_unmodified_since_from_headers(&headers), None);
+    }
+
+    #[test]
+    fn test_non_empty_list_ignores_empty_list_status() {
+        assert!(check_empty_list(&[1], EmptyListStatus::NotFound, "items").is_ok());
+    }
 }
@@ -2,75 +2,397 @@
 // HTTP handlers for post management operations
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::Deserialize;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::info;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
+use super::{check_empty_list, if_unmodified_since_from_headers, list_response, paginated_headers};
 use crate::{
+    config::Config,
     db::Database,
-    error::ApiError,
-    models::post::CreatePostRequest,
+    error::{ApiError, ErrorResponse},
+    extract::{ApiJson, ApiPath},
+    models::{
+        pagination::{PageInfo, Pagination, PaginationParams},
+        post::{
+            month_day_in_offset, parse_embed, validate_truncate, CreatePostRequest, CreatedAtRange, Post, PostContent,
+            PostPreview, PostWithAuthor, UpdatePostRequest,
+        },
+        user::User,
+    },
 };
 
 /// `GET /api/posts` のクエリパラメータを表す構造体。
 /// `Option<Uuid>` にすることで、存在しない場合は全件取得と同じ挙動になる。
-#[derive(Debug, Deserialize)]
+/// `include_deleted=true` を付けると、論理削除済みユーザーの投稿も含める。
+/// `from`/`to` は文字列のまま受け取り、`CreatedAtRange::parse` で JSON のバリデーションエラーにする。
+/// `limit`/`offset` は `Pagination` 抽出器が読む。
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListPostsQuery {
+    /// 指定したユーザーの投稿だけに絞り込む
+    pub user_id: Option<Uuid>,
+    /// この日時 (RFC3339) 以降に作成された投稿だけを返す
+    pub from: Option<String>,
+    /// この日時 (RFC3339) 以前に作成された投稿だけを返す
+    pub to: Option<String>,
+    /// 論理削除済みユーザーの投稿も含める
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// `true` なら配列の代わりに `{"data": [...], "page": {"limit", "offset", "total"}}` で返す
+    #[serde(default)]
+    pub envelope: bool,
+    /// `author` を指定すると、各投稿に作者 (`id`, `name`) を `author` として埋め込む
+    pub embed: Option<String>,
+    /// 本文をこの文字数までに切り詰め (末尾に `…`)、`content_truncated` を付ける。1 以上
+    pub truncate: Option<usize>,
+}
+
+/// `GET /api/posts/:id` のクエリパラメータ。
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetPostQuery {
+    /// `author` を指定すると、作者 (`id`, `name`) を `author` として埋め込む
+    pub embed: Option<String>,
+    /// 本文をこの文字数までに切り詰め (末尾に `…`)、`content_truncated` を付ける。1 以上
+    pub truncate: Option<usize>,
+}
+
+/// `get_all_posts` が返す一覧。`embed=author` の有無でレスポンスの要素の形が変わる。
+enum PostList {
+    Plain(Vec<Post>),
+    WithAuthor(Vec<PostWithAuthor>),
+}
+
+impl PostList {
+    fn len(&self) -> usize {
+        match self {
+            PostList::Plain(posts) => posts.len(),
+            PostList::WithAuthor(posts) => posts.len(),
+        }
+    }
+}
+
+/// `truncate` があれば本文を切り詰めた `PostPreview` で、なければ投稿をそのまま返す。
+fn post_response<T: PostContent + Serialize>(post: T, truncate: Option<usize>) -> Response {
+    match truncate {
+        Some(max_chars) => (StatusCode::OK, Json(PostPreview::new(post, max_chars))).into_response(),
+        None => (StatusCode::OK, Json(post)).into_response(),
+    }
+}
+
+/// 一覧版の `post_response`。各要素に同じ切り詰めを適用してから `list_response` に渡す。
+fn post_list_response<T: PostContent + Serialize>(
+    posts: Vec<T>,
+    truncate: Option<usize>,
+    page: Option<PageInfo>,
+) -> Response {
+    match truncate {
+        Some(max_chars) => {
+            let previews: Vec<PostPreview<T>> = posts.into_iter().map(|post| PostPreview::new(post, max_chars)).collect();
+            list_response(previews, page)
+        }
+        None => list_response(posts, page),
+    }
+}
+
+/// `GET /api/posts/on-this-day` のクエリパラメータ。
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OnThisDayQuery {
+    /// 指定したユーザーの投稿だけに絞り込む
     pub user_id: Option<Uuid>,
 }
 
 /// `POST /api/posts`
 /// リクエストボディは JSON として受け取り、`CreatePostRequest` のバリデーション結果に従う。
+#[utoipa::path(
+    post,
+    path = "/api/posts",
+    tag = "posts",
+    request_body = CreatePostRequest,
+    responses(
+        (status = 201, description = "Post created", body = Post),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
 pub async fn create_post(
     State(db): State<Arc<Database>>,
-    Json(request): Json<CreatePostRequest>,
+    State(config): State<Arc<Config>>,
+    ApiJson(request): ApiJson<CreatePostRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     info!("Creating new post for user_id: {} with title: {}", request.user_id, request.title);
     
-    let post = db.create_post(request).await?;
+    let post = db.create_post(request, &config.post_limits).await?;
     
     info!("Successfully created post with id: {}", post.id);
     Ok((StatusCode::CREATED, Json(post)))
 }
 
-/// `GET /api/posts/:id`
-/// パスパラメータを `Uuid` として受け取り、そのまま DB レイヤーへ委譲する。
+/// `GET /api/posts/:id?embed=author&truncate=<n>`
+/// パスパラメータは文字列のまま DB レイヤーへ委譲する。不正な ID (400) と存在しない ID (404) の区別は
+/// DB レイヤーに任せ、どちらも同じ JSON エラー形式で返す。
+/// `embed=author` のときだけ作者を埋め込んだ `PostWithAuthor` を返し、指定がなければ従来どおりの `Post`。
+/// `truncate` を付けると本文を n 文字に切り詰め、`content_truncated` を加える。
+#[utoipa::path(
+    get,
+    path = "/api/posts/{id}",
+    tag = "posts",
+    params(("id" = Uuid, Path, description = "Post ID"), GetPostQuery),
+    responses(
+        (status = 200, description = "Post found. With `embed=author` the post also carries an `author` object (see `PostWithAuthor`); with `truncate=<n>` `content` is cut to n characters plus `…` and a `content_truncated` boolean is added", body = Post),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_post_by_id(
     State(db): State<Arc<Database>>,
-    Path(post_id): Path<Uuid>,
+    ApiPath(post_id): ApiPath<String>,
+    Query(params): Query<GetPostQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let embed_author = parse_embed(params.embed.as_deref()).map_err(ApiError::Validation)?;
+    let truncate = validate_truncate(params.truncate).map_err(ApiError::Validation)?;
+    info!("Fetching post with id: {} (embed author: {})", post_id, embed_author);
+
+    if embed_author {
+        let post = db.get_post_with_author_by_id(&post_id).await?;
+        return Ok(post_response(post, truncate));
+    }
+    let post = db.get_post_by_id(&post_id).await?;
+
+    Ok(post_response(post, truncate))
+}
+
+/// `GET /api/posts/:id/author`
+/// 投稿の作者を返す。投稿表示のたびに `GET /api/users/:id` を追加で呼ばずに済むよう、DB 側で JOIN している。
+#[utoipa::path(
+    get,
+    path = "/api/posts/{id}/author",
+    tag = "posts",
+    params(("id" = Uuid, Path, description = "Post ID")),
+    responses(
+        (status = 200, description = "Author of the post", body = User),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Post not found, or its author no longer exists", body = ErrorResponse),
+    )
+)]
+pub async fn get_post_author(
+    State(db): State<Arc<Database>>,
+    ApiPath(post_id): ApiPath<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    info!("Fetching post with id: {}", post_id);
+    info!("Fetching author of post with id: {}", post_id);
+
+    let author = db.get_post_author(&post_id).await?;
+
+    Ok((StatusCode::OK, Json(author)))
+}
+
+/// `PUT /api/posts/:id`
+/// ボディの `expected_version` が現在の `version` と一致する場合だけ更新する (楽観ロック)。
+/// 他のクライアントが先に更新していれば 409 を返すので、クライアントは読み直してから再送する。
+/// `If-Unmodified-Since` を付けると、その時刻より後に更新されていた場合は 412 を返して更新しない。
+#[utoipa::path(
+    put,
+    path = "/api/posts/{id}",
+    tag = "posts",
+    params(
+        ("id" = Uuid, Path, description = "Post ID"),
+        ("If-Unmodified-Since" = Option<String>, Header, description = "HTTP date; the update is rejected with 412 if the post changed after it"),
+    ),
+    request_body = UpdatePostRequest,
+    responses(
+        (status = 200, description = "Post updated; `version` is incremented", body = Post),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Post not found", body = ErrorResponse),
+        (status = 409, description = "`expected_version` does not match the current version", body = ErrorResponse),
+        (status = 412, description = "The post was modified after `If-Unmodified-Since`", body = ErrorResponse),
+    )
+)]
+pub async fn update_post(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    ApiPath(post_id): ApiPath<String>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<UpdatePostRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Updating post with id: {} (expected version: {})", post_id, request.expected_version);
     
-    let post = db.get_post_by_id(&post_id.to_string()).await?;
+    let post = db.update_post(&post_id, request, &config.post_limits, if_unmodified_since_from_headers(&headers)).await?;
     
+    info!("Successfully updated post with id: {}", post_id);
     Ok((StatusCode::OK, Json(post)))
 }
 
-/// `GET /api/posts?user_id=<id>`
-/// クエリの有無でログメッセージを変える例。戻り値は常に 200 OK + JSON 配列。
+/// `GET /api/posts?user_id=<id>&from=<RFC3339>&to=<RFC3339>&limit=<n>&offset=<m>&embed=author`
+/// クエリの有無でログメッセージを変える例。`from > to` は 400 (VALIDATION_ERROR)。
+/// `embed=author` なら作者を JOIN で一緒に取得し、各要素を `PostWithAuthor` の形で返す。
+/// `truncate=<n>` なら各要素の本文を n 文字に切り詰め、`content_truncated` を加える。
+/// 新しい順に 1 ページ分を返し、同じ条件に合う全件数を `X-Total-Count` ヘッダーに入れる。戻り値は 200 OK + JSON 配列
+/// (0 件かつ `EMPTY_LIST_STATUS=not_found` の場合のみ 404)。
+#[utoipa::path(
+    get,
+    path = "/api/posts",
+    tag = "posts",
+    params(ListPostsQuery, PaginationParams),
+    responses(
+        (
+            status = 200,
+            description = "One page of posts, newest first. With `envelope=true` the array is wrapped as `{ data, page: PageInfo }`; with `embed=author` each post also carries an `author` object (see `PostWithAuthor`); with `truncate=<n>` each `content` is cut to n characters plus `…` and a `content_truncated` boolean is added",
+            body = Vec<Post>,
+            headers(
+                ("x-total-count" = i64, description = "Number of posts matching the filters across all pages"),
+                ("x-page-size-clamped" = String, description = "Set to `true` when `limit` was clamped to the maximum"),
+            )
+        ),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "No posts found (only with EMPTY_LIST_STATUS=not_found)", body = ErrorResponse),
+    )
+)]
 pub async fn get_all_posts(
     State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
     Query(params): Query<ListPostsQuery>,
+    pagination: Pagination,
 ) -> Result<impl IntoResponse, ApiError> {
+    let created_range = CreatedAtRange::parse(params.from.as_deref(), params.to.as_deref())
+        .map_err(ApiError::Validation)?;
+    let embed_author = parse_embed(params.embed.as_deref()).map_err(ApiError::Validation)?;
+    let truncate = validate_truncate(params.truncate).map_err(ApiError::Validation)?;
+    let Pagination { limit, offset, .. } = pagination;
+    
     if let Some(ref user_id) = params.user_id {
-        info!("Fetching posts for user_id: {}", user_id);
+        info!("Fetching posts for user_id: {} (created range: {:?})", user_id, created_range);
     } else {
-        info!("Fetching all posts");
+        info!("Fetching all posts (created range: {:?})", created_range);
     }
     
-    let posts = db.get_all_posts(params.user_id.as_ref().map(|id| id.to_string()).as_deref()).await?;
-    
+    let user_id_filter = params.user_id.as_ref().map(|id| id.to_string());
+    let posts = if embed_author {
+        PostList::WithAuthor(db.get_all_posts_with_author(
+            user_id_filter.as_deref(),
+            &created_range,
+            params.include_deleted,
+            limit,
+            offset,
+        ).await?)
+    } else {
+        PostList::Plain(db.get_all_posts(
+            user_id_filter.as_deref(),
+            &created_range,
+            params.include_deleted,
+            limit,
+            offset,
+        ).await?)
+    };
+    let total = db.count_posts(user_id_filter.as_deref(), &created_range, params.include_deleted).await?;
+
     if let Some(user_id) = params.user_id {
-        info!("Retrieved {} posts for user_id: {}", posts.len(), user_id);
+        info!("Retrieved {} of {} posts for user_id: {}", posts.len(), total, user_id);
     } else {
-        info!("Retrieved {} posts", posts.len());
+        info!("Retrieved {} of {} posts", posts.len(), total);
     }
+
+    let headers = paginated_headers(total, pagination.clamped, config.page_size_clamp_header);
+    let page = params.envelope.then_some(PageInfo { limit, offset, total });
+    let body = match posts {
+        PostList::Plain(posts) => {
+            check_empty_list(&posts, config.empty_list_status, "posts")?;
+            post_list_response(posts, truncate, page)
+        }
+        PostList::WithAuthor(posts) => {
+            check_empty_list(&posts, config.empty_list_status, "posts")?;
+            post_list_response(posts, truncate, page)
+        }
+    };
+    Ok((StatusCode::OK, headers, body))
+}
+
+/// `GET /api/posts/on-this-day?user_id=<id>`
+/// 「今日」と同じ月日に作成された過去の投稿を返す。今日の判定は `APP_TIMEZONE_OFFSET` に従う。
+#[utoipa::path(
+    get,
+    path = "/api/posts/on-this-day",
+    tag = "posts",
+    params(OnThisDayQuery),
+    responses(
+        (status = 200, description = "Posts created on today's month and day in past years", body = Vec<Post>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "No posts found (only with EMPTY_LIST_STATUS=not_found)", body = ErrorResponse),
+    )
+)]
+pub async fn get_posts_on_this_day(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<OnThisDayQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (month, day) = month_day_in_offset(Utc::now(), config.timezone_offset);
+    info!("Fetching posts created on {:02}-{:02} (user_id: {:?})", month, day, params.user_id);
+    
+    let posts = db.get_posts_on_this_day(
+        month,
+        day,
+        config.timezone_offset.local_minus_utc(),
+        params.user_id.as_ref().map(|id| id.to_string()).as_deref(),
+    ).await?;
     
+    info!("Retrieved {} posts for on-this-day", posts.len());
+    check_empty_list(&posts, config.empty_list_status, "posts")?;
     Ok((StatusCode::OK, Json(posts)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        handlers::TOTAL_COUNT_HEADER,
+        state::AppState,
+        test_support::{test_app_state, user_request},
+    };
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn get_page(state: AppState, uri: String) -> (String, Vec<Uuid>) {
+        let response = Router::new()
+            .route("/api/posts", get(get_all_posts))
+            .with_state(state)
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let total = response.headers().get(TOTAL_COUNT_HEADER).unwrap().to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let posts: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let ids = posts.iter().map(|post| post["id"].as_str().unwrap().parse().unwrap()).collect();
+        (total, ids)
+    }
+
+    #[tokio::test]
+    async fn test_posts_are_sliced_into_pages_with_the_unpaged_total() {
+        let Some(state) = test_app_state(|_| {}).await else { return; };
+        let user = state.db.create_user(user_request("pager")).await.unwrap();
+        let mut created = Vec::new();
+        for i in 0..3 {
+            let request = CreatePostRequest { user_id: user.id, title: format!("Post {}", i), content: None };
+            created.push(state.db.create_post(request, &state.config.post_limits).await.unwrap().id);
+        }
+        // Newest first
+        created.reverse();
+
+        let (total, first) = get_page(state.clone(), format!("/api/posts?user_id={}&limit=2&offset=0", user.id)).await;
+        assert_eq!(total, "3");
+        assert_eq!(first, created[..2]);
+
+        let (total, second) = get_page(state, format!("/api/posts?user_id={}&limit=2&offset=2", user.id)).await;
+        assert_eq!(total, "3");
+        assert_eq!(second, created[2..]);
+    }
+}
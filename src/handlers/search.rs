@@ -0,0 +1,49 @@
+// Search handlers
+// HTTP handlers for searching across users, posts and vocabulary
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::{
+    db::Database,
+    error::{ApiError, ErrorResponse},
+    models::search::{SearchParams, SearchResults},
+};
+
+/// `GET /api/search?q=apple&type=all&limit=5`
+/// ユーザー・投稿・語彙をまとめて検索し、カテゴリごとに分けて返す。
+/// `type` で 1 カテゴリに絞った場合、他のカテゴリのキーはレスポンスに含めない。
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Matches grouped by category, at most `limit` per category", body = SearchResults),
+        (status = 400, description = "Blank or too long q, unknown type, or invalid limit", body = ErrorResponse),
+    )
+)]
+pub async fn search(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<SearchParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pattern = params.like_pattern().map_err(ApiError::Validation)?;
+    let search_type = params.search_type().map_err(ApiError::Validation)?;
+    let limit = params.limit().map_err(ApiError::Validation)?;
+
+    let results = db.search(&pattern, search_type, limit).await?;
+
+    info!(
+        "Search matched {} users, {} posts, {} vocabulary entries",
+        results.users.as_ref().map_or(0, Vec::len),
+        results.posts.as_ref().map_or(0, Vec::len),
+        results.vocabulary.as_ref().map_or(0, Vec::len)
+    );
+    Ok((StatusCode::OK, Json(results)))
+}
@@ -2,27 +2,78 @@
 // HTTP handlers for user management operations
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::info;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use super::{check_empty_list, if_unmodified_since_from_headers, list_response, page_size_clamped_headers};
 use crate::{
+    config::Config,
     db::Database,
-    error::ApiError,
-    models::user::{CreateUserRequest, UpdateUserRequest},
+    error::{ApiError, ErrorResponse},
+    extract::{ApiJson, ApiPath},
+    models::{
+        pagination::{PageInfo, Pagination, PaginationParams},
+        post::Post,
+        user::{
+            CreateUserRequest, CreateUserWithPostsRequest, UpdateUserRequest, UpdateUserResponse, User, UserSearchParams,
+            UserWithPostsResponse,
+        },
+    },
 };
 
+/// `GET /api/users` のクエリパラメータ。`limit`/`offset` は `Pagination` 抽出器が読む。
+/// `include_deleted=true` は論理削除済みユーザーも確認したい管理用途向け。
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQuery {
+    /// 論理削除済みユーザーも含める
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// `true` なら配列の代わりに `{"data": [...], "page": {"limit", "offset", "total"}}` で返す
+    #[serde(default)]
+    pub envelope: bool,
+}
+
+/// `DELETE /api/users/:id` のクエリパラメータ。
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteUserQuery {
+    /// 投稿を持つユーザーも削除する (投稿は非公開になる)
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// 投稿の付け替え結果。`transferred` は移した投稿の件数。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransferPostsResponse {
+    pub transferred: u64,
+}
+
 /// `POST /api/users`
 /// Axum の `State<Arc<Database>>`/`Json<T>` エクストラクタを使った典型的な作成ハンドラ。
 /// `db.create_user` が `Result` を返すため、`?` で早期リターンできる。
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 201, description = "User created", body = User),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 409, description = "Email address already exists", body = ErrorResponse),
+    )
+)]
 pub async fn create_user(
     State(db): State<Arc<Database>>,
-    Json(request): Json<CreateUserRequest>,
+    ApiJson(request): ApiJson<CreateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     info!("Creating new user with email: {}", request.email);
     
@@ -32,11 +83,48 @@ pub async fn create_user(
     Ok((StatusCode::CREATED, Json(user)))
 }
 
+/// `POST /api/users/with-posts`
+/// ユーザーと最初の投稿をまとめて作る。全部作れるか、何も作らないかのどちらか。
+#[utoipa::path(
+    post,
+    path = "/api/users/with-posts",
+    tag = "users",
+    request_body = CreateUserWithPostsRequest,
+    responses(
+        (status = 201, description = "User and posts created", body = UserWithPostsResponse),
+        (status = 400, description = "Invalid user or post (post errors are prefixed with `posts[<index>]`)", body = ErrorResponse),
+        (status = 409, description = "Email address already exists", body = ErrorResponse),
+    )
+)]
+pub async fn create_user_with_posts(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    ApiJson(request): ApiJson<CreateUserWithPostsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Creating new user with email: {} and {} posts", request.user.email, request.posts.len());
+    
+    let response = db.create_user_with_posts(request, &config.post_limits).await?;
+    
+    info!("Successfully created user with id: {}", response.user.id);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
 /// `GET /api/users/:id`
-/// `Path<Uuid>` によって UUID の妥当性チェックを Axum に任せられる例。
+/// `ApiPath<Uuid>` によって UUID の妥当性チェックを抽出器に任せられる例。不正な形式は JSON の 400 になる。
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User found", body = User),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_user_by_id(
     State(db): State<Arc<Database>>,
-    Path(user_id): Path<Uuid>,
+    ApiPath(user_id): ApiPath<Uuid>,
 ) -> Result<impl IntoResponse, ApiError> {
     info!("Fetching user with id: {}", user_id);
     
@@ -45,44 +133,220 @@ pub async fn get_user_by_id(
     Ok((StatusCode::OK, Json(user)))
 }
 
-/// `GET /api/users`
+/// `GET /api/users?include_deleted=true&limit=<n>&offset=<m>&envelope=true`
 /// 返り値は `Vec<User>` を JSON 化したもの。`info!` で件数をログに残している。
+/// 後方互換のため、ページング系のパラメータがなければ全件を配列で返す。
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    tag = "users",
+    params(ListUsersQuery, PaginationParams),
+    responses(
+        (
+            status = 200,
+            description = "List of users. With `envelope=true` the array is wrapped as `{ data, page: PageInfo }`",
+            body = Vec<User>,
+            headers(("x-page-size-clamped" = String, description = "Set to `true` when `limit` was clamped to the maximum"))
+        ),
+        (status = 400, description = "Invalid limit or offset", body = ErrorResponse),
+        (status = 404, description = "No users found (only with EMPTY_LIST_STATUS=not_found)", body = ErrorResponse),
+    )
+)]
 pub async fn get_all_users(
     State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<ListUsersQuery>,
+    pagination: Pagination,
 ) -> Result<impl IntoResponse, ApiError> {
-    info!("Fetching all users");
+    info!("Fetching all users (include_deleted: {})", params.include_deleted);
+    
+    let Pagination { limit, offset, .. } = pagination;
+    let paged = params.envelope || pagination.requested;
     
-    let users = db.get_all_users().await?;
+    let users = db.get_all_users(params.include_deleted, paged.then_some(limit), offset).await?;
+    let page = if params.envelope {
+        let total = db.count_users(params.include_deleted).await?;
+        Some(PageInfo { limit, offset, total })
+    } else {
+        None
+    };
     
     info!("Retrieved {} users", users.len());
-    Ok((StatusCode::OK, Json(users)))
+    check_empty_list(&users, config.empty_list_status, "users")?;
+    let headers = page_size_clamped_headers(pagination.clamped, config.page_size_clamp_header);
+    Ok((StatusCode::OK, headers, list_response(users, page)))
+}
+
+/// `GET /api/users/search?q=john&limit=20&offset=0`
+/// 管理用途のユーザー検索。名前・メールアドレスに部分一致したユーザーを新しい順に返す。
+#[utoipa::path(
+    get,
+    path = "/api/users/search",
+    tag = "users",
+    params(UserSearchParams, PaginationParams),
+    responses(
+        (status = 200, description = "Matching users", body = Vec<User>),
+        (status = 400, description = "Blank or too long q, or invalid limit or offset", body = ErrorResponse),
+    )
+)]
+pub async fn search_users(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<UserSearchParams>,
+    pagination: Pagination,
+) -> Result<impl IntoResponse, ApiError> {
+    let pattern = params.like_pattern().map_err(ApiError::Validation)?;
+    
+    let users = db.search_users(&pattern, pagination.limit, pagination.offset).await?;
+    
+    info!("User search matched {} users", users.len());
+    let headers = page_size_clamped_headers(pagination.clamped, config.page_size_clamp_header);
+    Ok((StatusCode::OK, headers, Json(users)))
+}
+
+/// `GET /api/users/:id/posts`
+/// 先にユーザーの存在を確認するので、「ユーザーがいない (404)」と「投稿が 0 件 (空配列)」を区別できる。
+/// ただし `EMPTY_LIST_STATUS=not_found` の場合は投稿 0 件も 404 になる。
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/posts",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Posts written by the user", body = Vec<Post>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_user_posts(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    ApiPath(user_id): ApiPath<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Fetching posts for user with id: {}", user_id);
+    
+    let user_id = user_id.to_string();
+    db.get_user_by_id(&user_id).await?;
+    let posts = db.get_posts_by_user_id(&user_id).await?;
+    
+    info!("Retrieved {} posts for user with id: {}", posts.len(), user_id);
+    check_empty_list(&posts, config.empty_list_status, "posts")?;
+    Ok((StatusCode::OK, Json(posts)))
 }
 
 /// `PUT /api/users/:id`
-/// `Json<UpdateUserRequest>` が Option フィールドを含む点に注目。
+/// `ApiJson<UpdateUserRequest>` が Option フィールドを含む点に注目。
+/// 本文に `id` が含まれていてパスの ID と異なる場合は、どちらを更新するか曖昧なので 400 にする。
+/// メールアドレスが変わった場合は `email_verified` が `false` に戻り、`email_verification_required: true` を返す。
+/// `If-Unmodified-Since` を付けると、その時刻より後に更新されていた場合は 412 を返して更新しない。
+#[utoipa::path(
+    put,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+        ("If-Unmodified-Since" = Option<String>, Header, description = "HTTP date; the update is rejected with 412 if the user changed after it"),
+    ),
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "User updated, with whether the new email address needs to be verified again", body = UpdateUserResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 409, description = "Email address already exists", body = ErrorResponse),
+        (status = 412, description = "The user was modified after `If-Unmodified-Since`", body = ErrorResponse),
+    )
+)]
 pub async fn update_user(
     State(db): State<Arc<Database>>,
-    Path(user_id): Path<Uuid>,
-    Json(request): Json<UpdateUserRequest>,
+    ApiPath(user_id): ApiPath<Uuid>,
+    headers: HeaderMap,
+    ApiJson(request): ApiJson<UpdateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     info!("Updating user with id: {}", user_id);
+    request.check_path_id(user_id).map_err(ApiError::Validation)?;
     
-    let user = db.update_user(&user_id.to_string(), request).await?;
+    let response = db.update_user(&user_id.to_string(), request, if_unmodified_since_from_headers(&headers)).await?;
     
     info!("Successfully updated user with id: {}", user_id);
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// `POST /api/users/:id/verify-email`
+/// メールアドレスを確認済みにして、更新後のユーザーを返す。確認メールの送信やトークンの照合は扱わない。
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/verify-email",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Email address marked as verified", body = User),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn verify_user_email(
+    State(db): State<Arc<Database>>,
+    ApiPath(user_id): ApiPath<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Verifying email of user with id: {}", user_id);
+    
+    let user = db.verify_user_email(&user_id.to_string()).await?;
+    
     Ok((StatusCode::OK, Json(user)))
 }
 
 /// `DELETE /api/users/:id`
-/// 削除成功時は `StatusCode::NO_CONTENT` を返し、HTTP 的な慣習に従ってボディなしで応答する。
+/// 論理削除を行い、成功時は `StatusCode::NO_CONTENT` を返す。HTTP 的な慣習に従ってボディなしで応答する。
+/// 投稿を持つユーザーは `?force=true` を付けない限り 409 で拒否する。
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID"), DeleteUserQuery),
+    responses(
+        (status = 204, description = "User soft-deleted"),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 409, description = "User has posts and force=true was not given", body = ErrorResponse),
+    )
+)]
 pub async fn delete_user(
     State(db): State<Arc<Database>>,
-    Path(user_id): Path<Uuid>,
+    ApiPath(user_id): ApiPath<Uuid>,
+    Query(query): Query<DeleteUserQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
-    info!("Deleting user with id: {}", user_id);
+    info!("Deleting user with id: {} (force: {})", user_id, query.force);
     
-    db.delete_user(&user_id.to_string()).await?;
+    db.delete_user(&user_id.to_string(), query.force).await?;
     
-    info!("Successfully deleted user with id: {} (cascade deleted associated posts)", user_id);
+    info!("Successfully soft-deleted user with id: {}", user_id);
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// `POST /api/users/:id/transfer-posts/:to_id`
+/// アカウント統合のため、ユーザー `id` の投稿をすべて `to_id` に付け替える。
+/// どちらかのユーザーが存在しない (削除済みを含む) 場合は 404、同じユーザー同士なら 400。
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/transfer-posts/{to_id}",
+    tag = "users",
+    params(
+        ("id" = Uuid, Path, description = "User whose posts are moved"),
+        ("to_id" = Uuid, Path, description = "User who receives the posts"),
+    ),
+    responses(
+        (status = 200, description = "Posts reassigned", body = TransferPostsResponse),
+        (status = 400, description = "Invalid request, or both IDs name the same user", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
+pub async fn transfer_posts(
+    State(db): State<Arc<Database>>,
+    ApiPath((from_id, to_id)): ApiPath<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse, ApiError> {
+    info!("Transferring posts from user {} to user {}", from_id, to_id);
+    
+    let transferred = db.transfer_posts(from_id, to_id).await?;
+    
+    Ok((StatusCode::OK, Json(TransferPostsResponse { transferred })))
+}
@@ -2,69 +2,734 @@
 // HTTP handlers for vocabulary management operations
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use std::sync::Arc;
+use futures_util::{Stream, StreamExt};
+use std::{convert::Infallible, sync::Arc};
 use tracing::info;
 
+use super::{
+    broadcast_events, cache_control_headers, check_empty_list, json_array_body, json_array_response, list_response, page_size_clamped_headers,
+    session_id_from_headers,
+};
 use crate::{
+    config::Config,
     db::Database,
-    error::ApiError,
-    models::vocabulary::CreateVocabularyRequest,
+    error::{ApiError, ErrorResponse},
+    extract::{ApiJson, ApiPath},
+    recent::{prefer_unseen, RecentlyServed},
+    state::Shutdown,
+    models::{
+        fields::{IdFormatQuery, WithIdFormat},
+        pagination::{PageInfo, Pagination, PaginationParams},
+        review::ReviewRequest,
+        user::UserSummary,
+        vocabulary::{
+            validate_vocabulary_id, CoverageRequest, CoverageResponse, CreateVocabularyQuery,
+            CreateVocabularyRequest, CreateVocabularyResponse, FavoriteRequest, GetVocabularyQuery, LetterCount, RandomVocabularyParams,
+            RelatedVocabularyParams, TagCount, Vocabulary, UpdateVocabularyRequest, VocabularyBatchParams, VocabularyBatchResponse, VocabularyChanges,
+            VocabularyChangesParams, VocabularyExamples, VocabularyHistoryEntry, VocabularyListParams,
+        },
+    },
 };
 
 /// `POST /api/vocabulary`
 /// 英単語・和訳・例文を受け取って DB に保存する。`CreateVocabularyRequest` 内で入力検証を行う。
+/// 同じ英単語 (大文字小文字は区別しない) が既にあれば 409、`?upsert=true` なら既存行を更新して 200 を返す。
+#[utoipa::path(
+    post,
+    path = "/api/vocabulary",
+    tag = "vocabulary",
+    params(CreateVocabularyQuery, IdFormatQuery),
+    request_body = CreateVocabularyRequest,
+    responses(
+        (status = 201, description = "Vocabulary entry created", body = CreateVocabularyResponse),
+        (status = 200, description = "Existing entry updated (upsert=true)", body = CreateVocabularyResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 409, description = "Word already exists (English words are compared case-insensitively)", body = ErrorResponse),
+    )
+)]
 pub async fn create_vocabulary(
     State(db): State<Arc<Database>>,
-    Json(request): Json<CreateVocabularyRequest>,
+    Query(query): Query<CreateVocabularyQuery>,
+    Query(id_format): Query<IdFormatQuery>,
+    ApiJson(request): ApiJson<CreateVocabularyRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    info!("Creating new vocabulary entry: {} -> {}", request.en_word, request.ja_word);
+    info!(
+        "Creating new vocabulary entry: {} -> {} (upsert: {})",
+        request.en_word, request.ja_word, query.upsert
+    );
     
-    let vocabulary = db.create_vocabulary(request).await?;
+    let response = db.create_vocabulary(request, query.upsert).await?;
     
-    info!("Successfully created vocabulary entry with id: {}", vocabulary.id);
-    Ok((StatusCode::CREATED, Json(vocabulary)))
+    let status = if response.inserted { StatusCode::CREATED } else { StatusCode::OK };
+    info!(
+        "Successfully {} vocabulary entry with id: {}",
+        if response.inserted { "created" } else { "updated" },
+        response.vocabulary.id
+    );
+    Ok((status, Json(WithIdFormat(response, id_format.id_format()))))
 }
 
-/// `GET /api/vocabulary/:id`
-/// `Path<i32>` により、整数変換エラー時は Axum が自動で 400 を返す。
+/// `GET /api/vocabulary/:id?fields=id,en_word&id_as_string=true`
+/// `ApiPath<i32>` により、整数変換エラー時は `VALIDATION_ERROR` の 400 を返す。
+/// 0 以下の ID も DB に問い合わせずにバリデーションエラーにする。
+/// `fields` を指定すると、そのフィールドだけを返す。成功時は `VOCAB_CACHE_MAX_AGE` の `Cache-Control` を付ける。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/{id}",
+    tag = "vocabulary",
+    params(("id" = i32, Path, description = "Vocabulary ID"), GetVocabularyQuery),
+    responses(
+        (
+            status = 200,
+            description = "Vocabulary entry found",
+            body = Vocabulary,
+            headers(("cache-control" = String, description = "`public, max-age=<VOCAB_CACHE_MAX_AGE>` unless disabled"))
+        ),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_vocabulary_by_id(
     State(db): State<Arc<Database>>,
-    Path(id): Path<i32>,
+    State(config): State<Arc<Config>>,
+    ApiPath(id): ApiPath<i32>,
+    Query(query): Query<GetVocabularyQuery>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    let fields = query.field_selection().map_err(ApiError::Validation)?;
+    let id_format = query.id_format();
     info!("Fetching vocabulary entry with id: {}", id);
     
     let vocabulary = db.get_vocabulary_by_id(id).await?;
     
-    Ok((StatusCode::OK, Json(vocabulary)))
+    let body = fields.project(&WithIdFormat(&vocabulary, id_format)).map_err(|e| ApiError::Internal(e.into()))?;
+    Ok((StatusCode::OK, cache_control_headers(config.vocab_cache_max_age), Json(body)))
+}
+
+/// `PATCH /api/vocabulary/:id`
+/// 送ったフィールドだけを更新する。例文はキーを省略すると変更なし、`null` を送ると削除になる。
+#[utoipa::path(
+    patch,
+    path = "/api/vocabulary/{id}",
+    tag = "vocabulary",
+    params(("id" = i32, Path, description = "Vocabulary ID"), IdFormatQuery),
+    request_body = UpdateVocabularyRequest,
+    responses(
+        (status = 200, description = "Vocabulary entry updated", body = Vocabulary),
+        (status = 400, description = "Invalid id or request body", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+        (status = 409, description = "The new en_word already exists for this language", body = ErrorResponse),
+    )
+)]
+pub async fn update_vocabulary(
+    State(db): State<Arc<Database>>,
+    ApiPath(id): ApiPath<i32>,
+    Query(id_format): Query<IdFormatQuery>,
+    ApiJson(request): ApiJson<UpdateVocabularyRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    info!("Updating vocabulary entry with id: {}", id);
+
+    let vocabulary = db.update_vocabulary(id, request).await?;
+
+    Ok((StatusCode::OK, Json(WithIdFormat(vocabulary, id_format.id_format()))))
+}
+
+/// `GET /api/vocabulary/batch?ids=1,2,3`
+/// 指定した ID の語彙をまとめて返す (クライアントのオフラインキャッシュ同期向け)。
+/// 存在しない ID があっても 404 にはせず、`missing_ids` に入れて返す。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/batch",
+    tag = "vocabulary",
+    params(VocabularyBatchParams, IdFormatQuery),
+    responses(
+        (status = 200, description = "Found entries in request order, plus ids that don't exist", body = VocabularyBatchResponse),
+        (status = 400, description = "Missing, non-integer or too many ids", body = ErrorResponse),
+    )
+)]
+pub async fn get_vocabulary_batch(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<VocabularyBatchParams>,
+    Query(id_format): Query<IdFormatQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let ids = params.parse_ids().map_err(ApiError::Validation)?;
+    info!("Fetching {} vocabulary entries by id", ids.len());
+    
+    let found = db.get_vocabulary_by_ids(&ids).await?;
+    let response = VocabularyBatchResponse::new(&ids, found);
+    
+    info!("Batch fetch found {} entries, {} missing", response.vocabulary.len(), response.missing_ids.len());
+    Ok((StatusCode::OK, Json(WithIdFormat(response, id_format.id_format()))))
 }
 
-/// `GET /api/vocabulary`
-/// 全件を配列で返す。`info!` で件数をログに残しておくと、モニタリング時に便利。
+/// `GET /api/vocabulary/:id/examples`
+/// 例文だけを返す軽量版。語彙の取得は `get_vocabulary_by_id` と同じ (キャッシュも共有する)。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/{id}/examples",
+    tag = "vocabulary",
+    params(("id" = i32, Path, description = "Vocabulary ID")),
+    responses(
+        (status = 200, description = "Example sentences (null when absent)", body = VocabularyExamples),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_vocabulary_examples(
+    State(db): State<Arc<Database>>,
+    ApiPath(id): ApiPath<i32>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    info!("Fetching examples for vocabulary entry with id: {}", id);
+    
+    let vocabulary = db.get_vocabulary_by_id(id).await?;
+    
+    Ok((StatusCode::OK, Json(VocabularyExamples::from(vocabulary))))
+}
+
+/// `GET /api/vocabulary/:id/history`
+/// `PATCH` や `?upsert=true` の `POST` で上書きされる前の値を新しい順に返す。語彙 1 件あたり直近 `VOCABULARY_HISTORY_LIMIT` 件まで。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/{id}/history",
+    tag = "vocabulary",
+    params(("id" = i32, Path, description = "Vocabulary ID"), IdFormatQuery),
+    responses(
+        (status = 200, description = "Values before each update, newest first (empty if never updated)", body = Vec<VocabularyHistoryEntry>),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_vocabulary_history(
+    State(db): State<Arc<Database>>,
+    ApiPath(id): ApiPath<i32>,
+    Query(id_format): Query<IdFormatQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    info!("Fetching history for vocabulary entry with id: {}", id);
+    
+    let history = db.get_vocabulary_history(id).await?;
+    
+    info!("Retrieved {} history entries for vocabulary entry with id: {}", history.len(), id);
+    Ok((StatusCode::OK, Json(WithIdFormat(history, id_format.id_format()))))
+}
+
+/// `POST /api/vocabulary/:id/review`
+/// 自己評価 (0〜5) を受け取り、SM-2 で更新した復習状態を含む語彙を返す。
+#[utoipa::path(
+    post,
+    path = "/api/vocabulary/{id}/review",
+    tag = "vocabulary",
+    params(("id" = i32, Path, description = "Vocabulary ID"), IdFormatQuery),
+    request_body = ReviewRequest,
+    responses(
+        (status = 200, description = "Review recorded; the entry with its new schedule", body = Vocabulary),
+        (status = 400, description = "Invalid id or grade outside 0-5", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+    )
+)]
+pub async fn review_vocabulary(
+    State(db): State<Arc<Database>>,
+    ApiPath(id): ApiPath<i32>,
+    Query(id_format): Query<IdFormatQuery>,
+    ApiJson(request): ApiJson<ReviewRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    info!("Recording review of vocabulary entry {} with grade {}", id, request.grade);
+    
+    let vocabulary = db.review_vocabulary(id, request).await?;
+    
+    Ok((StatusCode::OK, Json(WithIdFormat(vocabulary, id_format.id_format()))))
+}
+
+/// `POST /api/vocabulary/:id/favorite`
+/// 本文の `favorite` に合わせて語彙のスターを付け外しし、更新後の語彙を返す。何度呼んでも同じ結果になる。
+/// `user_id` を付けると、そのユーザーのお気に入りとして記録・解除し、スターは誰かがお気に入りにしている間だけ付く。
+#[utoipa::path(
+    post,
+    path = "/api/vocabulary/{id}/favorite",
+    tag = "vocabulary",
+    params(("id" = i32, Path, description = "Vocabulary ID"), IdFormatQuery),
+    request_body = FavoriteRequest,
+    responses(
+        (status = 200, description = "Favorite flag set; the updated entry", body = Vocabulary),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry or user not found", body = ErrorResponse),
+    )
+)]
+pub async fn set_vocabulary_favorite(
+    State(db): State<Arc<Database>>,
+    ApiPath(id): ApiPath<i32>,
+    Query(id_format): Query<IdFormatQuery>,
+    ApiJson(request): ApiJson<FavoriteRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    info!("Setting favorite of vocabulary entry {} to {}", id, request.favorite);
+    
+    let vocabulary = db.set_vocabulary_favorite(id, request.favorite, request.user_id).await?;
+    
+    Ok((StatusCode::OK, Json(WithIdFormat(vocabulary, id_format.id_format()))))
+}
+
+/// `GET /api/vocabulary/due?limit=<n>&offset=<m>`
+/// 復習期限を過ぎた語彙を期限の古い順に返す。まだ一度も復習していない語彙は含まない。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/due",
+    tag = "vocabulary",
+    params(PaginationParams, IdFormatQuery),
+    responses(
+        (status = 200, description = "Entries due for review, oldest due first", body = Vec<Vocabulary>, headers(("x-page-size-clamped" = String, description = "Set to `true` when the requested size was clamped to the maximum"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Nothing due (only with EMPTY_LIST_STATUS=not_found)", body = ErrorResponse),
+    )
+)]
+pub async fn get_due_vocabulary(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    Query(id_format): Query<IdFormatQuery>,
+    pagination: Pagination,
+) -> Result<impl IntoResponse, ApiError> {
+    let vocabulary_list = db.get_due_vocabulary(pagination.limit, pagination.offset).await?;
+    
+    info!("Retrieved {} vocabulary entries due for review", vocabulary_list.len());
+    check_empty_list(&vocabulary_list, config.empty_list_status, "vocabulary entries due for review")?;
+    let headers = page_size_clamped_headers(pagination.clamped, config.page_size_clamp_header);
+    Ok((StatusCode::OK, headers, Json(WithIdFormat(vocabulary_list, id_format.id_format()))))
+}
+
+/// `GET /api/vocabulary?sort=en_word&order=asc&starts_with=ap&lang=ja&tag=food&limit=<n>&offset=<m>&envelope=true&fields=id,en_word`
+/// 条件に合う語彙を配列で返す。不正な `sort`/`order`/`fields` は 400 (VALIDATION_ERROR) になる。
+/// 後方互換のため、ページング系のパラメータがなければ全件を返す。
+/// 語彙はめったに変わらないので、成功時は `VOCAB_CACHE_MAX_AGE` の `Cache-Control` を付ける。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary",
+    tag = "vocabulary",
+    params(VocabularyListParams, PaginationParams),
+    responses(
+        (
+            status = 200,
+            description = "List of vocabulary entries. With `envelope=true` the array is wrapped as `{ data, page: PageInfo }`",
+            body = Vec<Vocabulary>,
+            headers(
+                ("x-page-size-clamped" = String, description = "Set to `true` when `limit` was clamped to the maximum"),
+                ("cache-control" = String, description = "`public, max-age=<VOCAB_CACHE_MAX_AGE>` unless disabled")
+            )
+        ),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "No entries found (only with EMPTY_LIST_STATUS=not_found)", body = ErrorResponse),
+    )
+)]
 pub async fn get_all_vocabulary(
     State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<VocabularyListParams>,
+    pagination: Pagination,
 ) -> Result<impl IntoResponse, ApiError> {
-    info!("Fetching all vocabulary entries");
+    info!("Fetching vocabulary entries with params: {:?}", params);
     
-    let vocabulary_list = db.get_all_vocabulary().await?;
+    let Pagination { limit, offset, .. } = pagination;
+    let fields = params.field_selection().map_err(ApiError::Validation)?;
+    let id_format = params.id_format();
+    let paged = params.envelope || pagination.requested;
+    
+    let vocabulary_list = db.get_all_vocabulary(&params, paged.then_some(limit), offset).await?;
+    let page = if params.envelope {
+        let total = db.count_vocabulary(&params).await?;
+        Some(PageInfo { limit, offset, total })
+    } else {
+        None
+    };
+    check_empty_list(&vocabulary_list, config.empty_list_status, "vocabulary entries")?;
     
     info!("Retrieved {} vocabulary entries", vocabulary_list.len());
-    Ok((StatusCode::OK, Json(vocabulary_list)))
+    let mut headers = page_size_clamped_headers(pagination.clamped, config.page_size_clamp_header);
+    headers.extend(cache_control_headers(config.vocab_cache_max_age));
+    if fields.is_all() {
+        let items: Vec<_> = vocabulary_list.into_iter().map(|vocabulary| WithIdFormat(vocabulary, id_format)).collect();
+        return Ok((StatusCode::OK, headers, list_response(items, page)));
+    }
+    let items: Vec<_> = vocabulary_list.iter().map(|vocabulary| WithIdFormat(vocabulary, id_format)).collect();
+    let projected = fields.project_all(&items).map_err(|e| ApiError::Internal(e.into()))?;
+    Ok((StatusCode::OK, headers, list_response(projected, page)))
+}
+
+/// `GET /api/vocabulary/stream?sort=en_word&starts_with=ap&fields=id,en_word`
+/// `GET /api/vocabulary` と同じ条件・同じ形の JSON 配列を、DB から読みながら少しずつ返す。
+/// 全件をメモリに溜めないので巨大な一覧向け。配列をそのまま流すため `envelope=true` は使えない。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/stream",
+    tag = "vocabulary",
+    params(VocabularyListParams, PaginationParams),
+    responses(
+        (status = 200, description = "Same array as `GET /api/vocabulary`, streamed with chunked transfer encoding", body = Vec<Vocabulary>),
+        (status = 400, description = "Invalid request, or `envelope=true`", body = ErrorResponse),
+    )
+)]
+pub async fn stream_vocabulary(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    Query(params): Query<VocabularyListParams>,
+    pagination: Pagination,
+) -> Result<Response, ApiError> {
+    if params.envelope {
+        return Err(ApiError::Validation("envelope is not supported when streaming".to_string()));
+    }
+    let fields = params.field_selection().map_err(ApiError::Validation)?;
+    let id_format = params.id_format();
+    info!("Streaming vocabulary entries with params: {:?}", params);
+    
+    let rows = db
+        .stream_all_vocabulary(&params, pagination.requested.then_some(pagination.limit), pagination.offset)
+        .await?;
+    
+    let headers = page_size_clamped_headers(pagination.clamped, config.page_size_clamp_header);
+    let body = if fields.is_all() {
+        json_array_body(rows.map(move |row| row.map(|vocabulary| WithIdFormat(vocabulary, id_format))))
+    } else {
+        json_array_body(rows.map(move |row| {
+            fields.project(&WithIdFormat(row?, id_format)).map_err(|e| ApiError::Internal(e.into()))
+        }))
+    };
+    Ok(json_array_response(headers, body))
 }
 
-/// `GET /api/vocabulary/random`
-/// 単語帳からランダムに 1 件取る。練習問題用のエンドポイント。
+/// `GET /api/vocabulary/events`
+/// 新しく作成された語彙を Server-Sent Events で流し続ける (1 件ごとに `data:` に `Vocabulary` の JSON)。
+/// このインスタンスで作成されたものだけが対象で、upsert による更新は流れない。
+/// サーバーのシャットダウンが始まるとストリームを閉じるので、クライアントは再接続すること。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/events",
+    tag = "vocabulary",
+    params(IdFormatQuery),
+    responses(
+        (status = 200, description = "`text/event-stream` with one `data:` event per newly created entry", body = Vocabulary, content_type = "text/event-stream"),
+    )
+)]
+pub async fn vocabulary_events(
+    State(db): State<Arc<Database>>,
+    State(shutdown): State<Shutdown>,
+    Query(id_format): Query<IdFormatQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    info!("Client subscribed to vocabulary events");
+    
+    // Ends at shutdown so an open stream doesn't hold graceful shutdown for the whole grace period
+    let events = broadcast_events(db.subscribe_created_vocabulary(), id_format.id_format(), shutdown.started());
+    // Keep-alive comments stop idle proxies from closing the connection between words
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// `GET /api/vocabulary/tags`
+/// 使われているタグを語彙数の多い順に返す。絞り込み用のタグ一覧を UI に出すためのもの。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/tags",
+    tag = "vocabulary",
+    responses(
+        (status = 200, description = "Distinct tags with the number of entries carrying each", body = Vec<TagCount>),
+    )
+)]
+pub async fn get_vocabulary_tags(
+    State(db): State<Arc<Database>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let tags = db.get_vocabulary_tags().await?;
+    
+    info!("Retrieved {} vocabulary tags", tags.len());
+    Ok((StatusCode::OK, Json(tags)))
+}
+
+/// `GET /api/vocabulary/index`
+/// 英単語の頭文字ごとの語彙数を返す。A–Z のジャンプナビを作るためのもので、英字以外で始まる語彙は `#` にまとめる。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/index",
+    tag = "vocabulary",
+    responses(
+        (status = 200, description = "Lowercase first letters with their entry counts, ordered by letter; entries not starting with a-z are grouped under `#`", body = Vec<LetterCount>),
+    )
+)]
+pub async fn get_vocabulary_index(
+    State(db): State<Arc<Database>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let index = db.get_vocabulary_index().await?;
+    
+    info!("Retrieved vocabulary index with {} letters", index.len());
+    Ok((StatusCode::OK, Json(index)))
+}
+
+/// `GET /api/vocabulary/changes?since=<RFC3339>`
+/// オフライン対応クライアント向けの差分同期。`since` 以降に作成・更新された語彙と、次回の `since` に使う
+/// `server_time` を返す。`since` を省略すると全件を返す (初回の完全同期)。境界付近の語彙は重複しうる。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/changes",
+    tag = "vocabulary",
+    params(VocabularyChangesParams, IdFormatQuery),
+    responses(
+        (status = 200, description = "Entries changed at or after `since`, oldest change first, and the cursor to pass as the next `since`; entries near the cursor can repeat, so dedupe by `id`", body = VocabularyChanges),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    )
+)]
+pub async fn get_vocabulary_changes(
+    State(db): State<Arc<Database>>,
+    Query(params): Query<VocabularyChangesParams>,
+    Query(id_format): Query<IdFormatQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let since = params.since().map_err(ApiError::Validation)?;
+
+    let (server_time, changed) = db.get_vocabulary_changed_since(since).await?;
+
+    info!("Retrieved {} vocabulary entries changed since {}", changed.len(), since);
+    let changes = VocabularyChanges { server_time, changed, deleted_ids: Vec::new() };
+    Ok((StatusCode::OK, Json(WithIdFormat(changes, id_format.id_format()))))
+}
+
+/// `GET /api/vocabulary/random?count=<n>`
+/// 単語帳からランダムに出題用の語彙を取る。`count` 省略時 (または 1) は後方互換のため単一オブジェクト、
+/// 2 以上なら重複なしの配列を返す。上限は `MAX_RANDOM_COUNT` 件で、丸めた場合は
+/// `X-Page-Size-Clamped: true` ヘッダーを付ける。
+/// `X-Session-Id` ヘッダーがあれば、そのセッションに直近で出題した語彙を避けるよう偏らせる
+/// (記録はインスタンスごとのメモリ上にあり、インスタンス間では共有されない)。
+/// `?exclude=1,2,3` でクライアント側から避けたい ID を渡すこともでき、こちらはインスタンスをまたいでも効く。
+/// `?seed=<u64>` を付けると、同じシードには同じ語彙を返す (テストや A/B 用の再現可能な出題)。
+/// このときはシードだけで結果が決まるよう、`X-Session-Id` は使わない (記録もしない)。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/random",
+    tag = "vocabulary",
+    params(
+        RandomVocabularyParams,
+        IdFormatQuery,
+        ("x-session-id" = Option<String>, Header, description = "Quiz session; recently served entries are avoided"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Random entries. A single object when `count` is omitted or 1, otherwise an array",
+            body = Vec<Vocabulary>,
+            headers(("x-page-size-clamped" = String, description = "Set to `true` when the requested size was clamped to the maximum"))
+        ),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "The vocabulary table is empty", body = ErrorResponse),
+    )
+)]
 pub async fn get_random_vocabulary(
     State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    State(recently_served): State<Arc<RecentlyServed>>,
+    request_headers: HeaderMap,
+    Query(params): Query<RandomVocabularyParams>,
+    Query(id_format): Query<IdFormatQuery>,
+) -> Result<Response, ApiError> {
+    let id_format = id_format.id_format();
+    let count = params.resolved_count().map_err(ApiError::Validation)?;
+    let exclude = params.exclude_ids().map_err(ApiError::Validation)?;
+    info!("Fetching {} random vocabulary entries (excluding {} ids)", count, exclude.len());
+    
+    // A seed alone decides the picks, so the session's recent entries don't shift them
+    let session_id = session_id_from_headers(&request_headers)
+        .filter(|_| recently_served.window() > 0 && params.seed.is_none());
+    
+    let mut vocabulary_list = if let Some(session_id) = session_id {
+        // Over-fetch by the number of recent picks so enough unseen entries are available
+        let recent_ids = recently_served.recent_ids(&session_id);
+        let candidates = db.get_random_vocabulary(count + recent_ids.len() as i64, &exclude, params.seed).await?;
+        let picked = prefer_unseen(candidates, &recent_ids, count as usize);
+        recently_served.record(&session_id, picked.iter().map(|vocabulary| vocabulary.id));
+        picked
+    } else {
+        db.get_random_vocabulary(count, &exclude, params.seed).await?
+    };
+    let headers = page_size_clamped_headers(params.is_clamped(), config.page_size_clamp_header);
+    
+    if params.wants_single() {
+        let vocabulary = vocabulary_list.remove(0);
+        info!("Retrieved random vocabulary: {} -> {}", vocabulary.en_word, vocabulary.ja_word);
+        return Ok((StatusCode::OK, headers, Json(WithIdFormat(vocabulary, id_format))).into_response());
+    }
+    
+    info!("Retrieved {} random vocabulary entries", vocabulary_list.len());
+    Ok((StatusCode::OK, headers, Json(WithIdFormat(vocabulary_list, id_format))).into_response())
+}
+
+/// `POST /api/vocabulary/coverage`
+/// 英文を単語に分割し、語彙に登録済みの単語・未登録の単語・カバー率を返す。多読支援向け。
+#[utoipa::path(
+    post,
+    path = "/api/vocabulary/coverage",
+    tag = "vocabulary",
+    request_body = CoverageRequest,
+    responses(
+        (status = 200, description = "Known and unknown words in the text", body = CoverageResponse),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+    )
+)]
+pub async fn get_vocabulary_coverage(
+    State(db): State<Arc<Database>>,
+    ApiJson(request): ApiJson<CoverageRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    request.validate().map_err(ApiError::Validation)?;
+    
+    let words = request.tokenize();
+    info!("Computing vocabulary coverage for {} distinct words", words.len());
+    
+    let matches = db.find_vocabulary_ids_by_words(&words).await?;
+    let coverage = CoverageResponse::from_matches(words, &matches);
+    
+    info!("Vocabulary coverage ratio: {:.3}", coverage.coverage_ratio);
+    Ok((StatusCode::OK, Json(coverage)))
+}
+
+/// `GET /api/vocabulary/:id/related?limit=<n>`
+/// 「似た単語」として、`en_word` の綴りが似ている語彙 (トライグラム類似度が 0.3 より大きいもの) を
+/// 似ている順に返す。似た語彙がなければ空配列 (`EMPTY_LIST_STATUS=not_found` なら 404)、語彙自体が存在しなければ 404。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/{id}/related",
+    tag = "vocabulary",
+    params(
+        ("id" = i32, Path, description = "Vocabulary ID"),
+        RelatedVocabularyParams,
+        IdFormatQuery,
+    ),
+    responses(
+        (status = 200, description = "Entries with a similar en_word, most similar first", body = Vec<Vocabulary>, headers(("x-page-size-clamped" = String, description = "Set to `true` when the requested size was clamped to the maximum"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_related_vocabulary(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    ApiPath(id): ApiPath<i32>,
+    Query(params): Query<RelatedVocabularyParams>,
+    Query(id_format): Query<IdFormatQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    let limit = params.resolved_limit().map_err(ApiError::Validation)?;
+    info!("Fetching vocabulary related to entry with id: {}", id);
+    
+    db.get_vocabulary_by_id(id).await?;
+    let related = db.get_related_vocabulary(id, limit).await?;
+    check_empty_list(&related, config.empty_list_status, "related vocabulary")?;
+    let headers = page_size_clamped_headers(params.is_clamped(), config.page_size_clamp_header);
+    
+    info!("Retrieved {} vocabulary entries related to entry with id: {}", related.len(), id);
+    Ok((StatusCode::OK, headers, Json(WithIdFormat(related, id_format.id_format()))))
+}
+
+/// `GET /api/vocabulary/:id/favorited-by?limit=<n>&offset=<m>`
+/// その語彙をお気に入り登録したユーザーを `{ id, name }` の配列で返す。誰もいなければ空配列
+/// (`EMPTY_LIST_STATUS=not_found` なら 404)、
+/// 語彙自体が存在しなければ 404。`limit` が上限に丸められた場合は `X-Page-Size-Clamped` を付ける。
+#[utoipa::path(
+    get,
+    path = "/api/vocabulary/{id}/favorited-by",
+    tag = "vocabulary",
+    params(
+        ("id" = i32, Path, description = "Vocabulary ID"),
+        PaginationParams,
+    ),
+    responses(
+        (status = 200, description = "Users who favorited the entry", body = Vec<UserSummary>, headers(("x-page-size-clamped" = String, description = "Set to `true` when the requested size was clamped to the maximum"))),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 404, description = "Vocabulary entry not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_vocabulary_favorited_by(
+    State(db): State<Arc<Database>>,
+    State(config): State<Arc<Config>>,
+    ApiPath(id): ApiPath<i32>,
+    pagination: Pagination,
 ) -> Result<impl IntoResponse, ApiError> {
-    info!("Fetching random vocabulary entry");
+    let id = validate_vocabulary_id(id).map_err(ApiError::Validation)?;
+    info!("Fetching users who favorited vocabulary entry with id: {}", id);
     
-    let vocabulary = db.get_random_vocabulary().await?;
+    db.get_vocabulary_by_id(id).await?;
+    let users = db.get_vocabulary_favorited_by(id, pagination.limit, pagination.offset).await?;
+    check_empty_list(&users, config.empty_list_status, "users")?;
+    let headers = page_size_clamped_headers(pagination.clamped, config.page_size_clamp_header);
     
-    info!("Retrieved random vocabulary: {} -> {}", vocabulary.en_word, vocabulary.ja_word);
-    Ok((StatusCode::OK, Json(vocabulary)))
+    info!("Retrieved {} users who favorited vocabulary entry with id: {}", users.len(), id);
+    Ok((StatusCode::OK, headers, Json(users)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::EmptyListStatus,
+        handlers::SESSION_ID_HEADER,
+        state::AppState,
+        test_support::{test_app_state, unique_suffix, vocabulary_request},
+    };
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn list_nothing(empty_list_status: EmptyListStatus) -> Option<(StatusCode, serde_json::Value)> {
+        let state = test_app_state(|config| config.empty_list_status = empty_list_status).await?;
+        // No entry starts with a fresh random prefix, so the list is empty
+        let uri = format!("/api/vocabulary?starts_with=none{}", unique_suffix());
+        let response = Router::new()
+            .route("/api/vocabulary", get(get_all_vocabulary))
+            .with_state(state)
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        Some((status, serde_json::from_slice(&body).unwrap()))
+    }
+
+    async fn random_pick(state: AppState, uri: &str) -> serde_json::Value {
+        let response = Router::new()
+            .route("/api/vocabulary/random", get(get_random_vocabulary))
+            .with_state(state)
+            .oneshot(Request::builder().uri(uri).header(SESSION_ID_HEADER, "quiz").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_returns_the_same_entry_within_a_session() {
+        let Some(state) = test_app_state(|_| {}).await else { return; };
+        let first = state.db.create_vocabulary(vocabulary_request(&format!("seeda{}", unique_suffix())), false).await.unwrap();
+        let second = state.db.create_vocabulary(vocabulary_request(&format!("seedb{}", unique_suffix())), false).await.unwrap();
+        assert_ne!(first.vocabulary.id, second.vocabulary.id);
+
+        // Without the seed taking precedence, the session would steer the second pick away from the first
+        let picked = random_pick(state.clone(), "/api/vocabulary/random?seed=42").await;
+        let again = random_pick(state, "/api/vocabulary/random?seed=42").await;
+        assert_eq!(picked["id"], again["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_empty_vocabulary_list_is_an_empty_array_by_default() {
+        let Some((status, body)) = list_nothing(EmptyListStatus::Ok).await else { return; };
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_empty_vocabulary_list_is_not_found_when_configured() {
+        let Some((status, body)) = list_nothing(EmptyListStatus::NotFound).await else { return; };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "NOT_FOUND");
+        assert!(body["error"]["message"].as_str().unwrap().starts_with("No vocabulary entries found"));
+    }
 }
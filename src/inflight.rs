@@ -0,0 +1,153 @@
+// In-flight request tracking
+// Remembers which requests are still running so a forced shutdown can report them
+
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// 処理中のリクエスト 1 件の情報。
+#[derive(Debug, Clone, PartialEq)]
+pub struct InFlightRequest {
+    pub method: Method,
+    pub path: String,
+    pub started_at: Instant,
+}
+
+impl InFlightRequest {
+    /// 処理を始めてからの経過時間。
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+/// 処理中のリクエストを記録するストア。
+/// シャットダウンの猶予時間を過ぎても終わらなかったリクエストをログに残すために使う。
+#[derive(Default)]
+pub struct InFlightRequests {
+    next_id: AtomicU64,
+    requests: Mutex<HashMap<u64, InFlightRequest>>,
+}
+
+/// `InFlightRequests::start` が返すガード。drop されると記録から外れる。
+pub struct InFlightGuard {
+    tracker: Arc<InFlightRequests>,
+    id: u64,
+}
+
+impl InFlightRequests {
+    /// 空のストアを作る。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// リクエストの処理開始を記録する。返したガードを処理が終わるまで保持すること。
+    pub fn start(self: &Arc<Self>, method: Method, path: String) -> InFlightGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = InFlightRequest {
+            method,
+            path,
+            started_at: Instant::now(),
+        };
+        self.lock().insert(id, request);
+
+        InFlightGuard {
+            tracker: Arc::clone(self),
+            id,
+        }
+    }
+
+    /// 処理中のリクエスト数。
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// 処理中のリクエストがないか。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 処理中のリクエストを、開始が古い順に返す。
+    pub fn snapshot(&self) -> Vec<InFlightRequest> {
+        let mut requests: Vec<InFlightRequest> = self.lock().values().cloned().collect();
+        requests.sort_by_key(|request| request.started_at);
+        requests
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u64, InFlightRequest>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.tracker.lock().remove(&self.id);
+    }
+}
+
+/// `axum::middleware::from_fn_with_state` 用のミドルウェア。ハンドラが応答を返すまでの間だけ記録する。
+pub async fn track_in_flight(
+    State(tracker): State<Arc<InFlightRequests>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let _guard = tracker.start(request.method().clone(), request.uri().path().to_string());
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_guard_removes_request_on_drop() {
+        let tracker = Arc::new(InFlightRequests::new());
+
+        let first = tracker.start(Method::GET, "/api/users".to_string());
+        let second = tracker.start(Method::POST, "/api/vocabulary".to_string());
+        assert_eq!(tracker.len(), 2);
+
+        let paths: Vec<String> = tracker.snapshot().into_iter().map(|request| request.path).collect();
+        assert_eq!(paths, vec!["/api/users".to_string(), "/api/vocabulary".to_string()]);
+
+        drop(first);
+        assert_eq!(tracker.snapshot()[0].method, Method::POST);
+
+        drop(second);
+        assert!(tracker.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_tracks_request_while_handler_runs() {
+        let tracker = Arc::new(InFlightRequests::new());
+        let observed = Arc::clone(&tracker);
+
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(move || async move { observed.snapshot()[0].path.clone() }),
+            )
+            .layer(from_fn_with_state(Arc::clone(&tracker), track_in_flight));
+
+        let response = app
+            .oneshot(Request::builder().uri("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"/slow");
+        assert!(tracker.is_empty());
+    }
+}
@@ -1,13 +1,25 @@
 // Library root for the Rust PostgreSQL API
 
+pub mod cache;
 pub mod config;
 pub mod db;
+pub mod diagnostics;
 pub mod error;
+pub mod extract;
 pub mod middleware;
 pub mod models;
+pub mod notify;
+pub mod openapi;
 pub mod handlers;
+pub mod inflight;
+pub mod recent;
+pub mod review_buffer;
+pub mod state;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 // Re-export commonly used types
 pub use db::Database;
 pub use error::ApiError;
+pub use state::AppState;
 pub use models::{User, CreateUserRequest, UpdateUserRequest, Post, CreatePostRequest};
\ No newline at end of file
@@ -1,23 +1,53 @@
 use axum::{
-    routing::{delete, get, post, put},
+    middleware::from_fn_with_state,
+    routing::{delete, get, patch, post, put},
     Router,
 };
-use std::{net::SocketAddr, sync::Arc};
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use std::{
+    future::{Future, IntoFuture},
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::signal;
-use tracing::{error, info};
+use tokio::sync::{broadcast, oneshot};
+use tracing::{debug, error, info, warn};
+use utoipa_swagger_ui::SwaggerUi;
 
 use word_rest_api::{
-    config::Config,
-    db::Database,
+    config::{Config, DatabaseConfig},
+    db::{with_startup_timeout, Database},
+    diagnostics::run_startup_check,
     handlers::{
-        health_check,
-        posts::{create_post, get_all_posts, get_post_by_id},
-        users::{create_user, delete_user, get_all_users, get_user_by_id, update_user},
-        vocabulary::{create_vocabulary, get_all_vocabulary, get_random_vocabulary, get_vocabulary_by_id},
+        admin::{auto_assign_vocabulary_difficulty, get_audit_log, reset_vocabulary},
+        build_info, health_check, method_not_allowed, readiness_check, route_not_found,
+        posts::{create_post, get_all_posts, get_post_author, get_post_by_id, get_posts_on_this_day, update_post},
+        search::search,
+        users::{
+            create_user, create_user_with_posts, delete_user, get_all_users, get_user_by_id, get_user_posts, search_users,
+            transfer_posts, update_user, verify_user_email,
+        },
+        vocabulary::{
+            create_vocabulary, get_all_vocabulary, get_due_vocabulary, get_random_vocabulary, get_vocabulary_by_id,
+            get_vocabulary_batch, get_vocabulary_coverage, get_vocabulary_examples, get_vocabulary_favorited_by,
+            get_vocabulary_history, get_related_vocabulary, get_vocabulary_changes, get_vocabulary_index,
+            get_vocabulary_tags, review_vocabulary, set_vocabulary_favorite, stream_vocabulary, update_vocabulary,
+            vocabulary_events,
+        },
     },
-    middleware::{create_middleware_stack, init_tracing},
+    inflight::{track_in_flight, InFlightRequests},
+    middleware::{create_cors_layer, create_middleware_stack, init_tracing, log_bodies, BodyLogging, BODY_LOG_MAX_BYTES},
+    models::vocabulary::load_seed_entries,
+    notify::VocabularyChange,
+    openapi::{api_doc, OPENAPI_JSON_PATH, SWAGGER_UI_PATH},
+    state::AppState,
 };
 
+/// 猶予時間切れで強制終了する前に、復習結果の書き出しとプールの後始末に使える最大時間。
+const FORCED_EXIT_CLEANUP_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// エントリーポイント。
 /// `#[tokio::main]` によって Tokio ランタイムを自動起動し、非同期関数でも `await`
 /// がそのまま書ける。ここでは設定読込→DB初期化→マイグレーション→ルーター生成→サーバ起動
@@ -30,6 +60,20 @@ async fn main() {
         std::process::exit(1);
     }
 
+    // `--check-db [DATABASE_URL]` only verifies the database settings and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--check-db") {
+        let exit_code = check_database(args.get(position + 1).map(String::as_str)).await;
+        std::process::exit(exit_code);
+    }
+
+    // `--check` loads the full configuration, checks the database and schema, prints a report and exits
+    if args.iter().any(|arg| arg == "--check") {
+        let report = run_startup_check().await;
+        print!("{}", report);
+        std::process::exit(report.exit_code());
+    }
+
     // Load configuration from environment
     let config = match Config::from_env() {
         Ok(config) => {
@@ -42,6 +86,43 @@ async fn main() {
         }
     };
 
+    info!(
+        "Database pool size: {} connections per instance ({})",
+        config.database.max_connections,
+        if config.database.pooler {
+            "through the pooler (DATABASE_POOLER)"
+        } else {
+            "direct connection; Neon recommends the -pooler endpoint when many instances or clients connect"
+        }
+    );
+    if let Some(warning) = config.database.pool_size_warning() {
+        warn!("{}", warning);
+    }
+
+    if config.log_bodies {
+        warn!("LOG_BODIES is enabled: request and response bodies are buffered and logged at debug level");
+    }
+
+    // Load the certificate before anything else, so a bad TLS_CERT_PATH/TLS_KEY_PATH fails startup right away
+    let tls = match &config.tls {
+        Some(tls) => match RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await {
+            Ok(rustls_config) => {
+                info!("HTTPS enabled with certificate {}", tls.cert_path.display());
+                Some(rustls_config)
+            }
+            Err(e) => {
+                error!(
+                    "Failed to load TLS certificate {} and key {}: {}",
+                    tls.cert_path.display(),
+                    tls.key_path.display(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // Initialize database connection pool
     let database = match Database::new(config.database.clone()).await {
         Ok(db) => {
@@ -54,8 +135,9 @@ async fn main() {
         }
     };
 
-    // Perform database health check during startup
-    if let Err(e) = database.health_check().await {
+    // Perform database health check during startup; a stalled connection must not hang startup forever
+    let health_check = database.health_check();
+    if let Err(e) = with_startup_timeout(config.database.connection_timeout, "Database health check", health_check).await {
         error!("Database health check failed during startup: {}", e);
         std::process::exit(1);
     }
@@ -68,14 +150,50 @@ async fn main() {
     }
     info!("Database migrations completed successfully");
 
-    // Seed vocabulary data
-    if let Err(e) = database.seed_vocabulary().await {
-        error!("Failed to seed vocabulary data: {}", e);
-        std::process::exit(1);
+    // Open pooled connections now so the first requests don't pay for connection setup
+    database.warmup(config.database.warmup_target()).await;
+
+    // Seed vocabulary data (the table must also be empty; see Database::seed_vocabulary)
+    if config.seed_vocabulary {
+        let entries = match load_seed_entries(config.vocabulary_seed_file.as_deref(), config.seed_vocabulary_count) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed to load vocabulary seed data: {}", e);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = database.seed_vocabulary(&entries).await {
+            error!("Failed to seed vocabulary data: {}", e);
+            std::process::exit(1);
+        }
+    } else {
+        info!(
+            "Skipping vocabulary seeding because SEED_VOCABULARY is disabled (environment: {})",
+            config.environment.as_str()
+        );
+    }
+
+    // Follow vocabulary writes from every instance (LISTEN/NOTIFY); not fatal if unavailable
+    match database.subscribe_changes().await {
+        Ok(changes) => {
+            tokio::spawn(apply_vocabulary_changes(database.clone(), changes));
+        }
+        Err(e) => warn!(
+            "Vocabulary change notifications are unavailable; cached entries won't see writes from other instances: {}",
+            e
+        ),
+    }
+
+    // Write buffered reviews in batches (REVIEW_WRITE_BEHIND)
+    if database.review_buffer().is_some() {
+        tokio::spawn(flush_reviews_periodically(database.clone()));
     }
 
     // Create the Axum router with all endpoints
-    let app = create_router(database);
+    let state = AppState::new(database.clone(), Arc::new(config.clone()));
+    let in_flight = state.in_flight.clone();
+    let shutdown_notice = state.shutdown.clone();
+    let app = create_router(state);
 
     // Create socket address
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -84,7 +202,7 @@ async fn main() {
     // Create the server with graceful shutdown
     let listener = match tokio::net::TcpListener::bind(addr).await {
         Ok(listener) => {
-            info!("Server listening on {}", addr);
+            info!("Server listening on {} ({})", addr, if tls.is_some() { "https" } else { "http" });
             listener
         }
         Err(e) => {
@@ -94,43 +212,289 @@ async fn main() {
     };
 
     // Start the server with graceful shutdown handling
-    if let Err(e) = axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-    {
-        error!("Server error: {}", e);
-        std::process::exit(1);
+    let (shutdown_started, shutdown_requested) = oneshot::channel();
+    let shutdown = async move {
+        shutdown_signal().await;
+        // Close open event streams so they don't hold the graceful shutdown open
+        shutdown_notice.trigger();
+        let _ = shutdown_started.send(());
+    };
+    let server: Pin<Box<dyn Future<Output = std::io::Result<()>> + Send>> = match tls {
+        None => Box::pin(axum::serve(listener, app).with_graceful_shutdown(shutdown).into_future()),
+        Some(rustls_config) => {
+            let listener = match listener.into_std() {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Failed to hand the listener on {} to the HTTPS server: {}", addr, e);
+                    std::process::exit(1);
+                }
+            };
+            let handle = Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown.await;
+                // No deadline of its own; the grace period below bounds it like the plain HTTP server
+                shutdown_handle.graceful_shutdown(None);
+            });
+            Box::pin(
+                axum_server::from_tcp_rustls(listener, rustls_config)
+                    .handle(handle)
+                    .serve(app.into_make_service()),
+            )
+        }
+    };
+
+    // Bound how long in-flight requests may keep the process alive once shutdown has begun
+    let grace_period = async {
+        match shutdown_requested.await {
+            Ok(()) => tokio::time::sleep(config.shutdown_timeout).await,
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = server => {
+            if let Err(e) = result {
+                error!("Server error: {}", e);
+                flush_buffered_reviews(&database).await;
+                database.close().await;
+                std::process::exit(1);
+            }
+        }
+        _ = grace_period => {
+            log_unfinished_requests(&in_flight, config.shutdown_timeout);
+            cleanup_before_forced_exit(&database).await;
+            std::process::exit(1);
+        }
     }
 
+    // Requests have finished, so no more reviews can be buffered; write the rest before closing the pool
+    flush_buffered_reviews(&database).await;
+
+    // Drain the connection pool so Neon doesn't see abruptly dropped connections
+    database.close().await;
+
     info!("Server shutdown complete");
 }
 
+/// シャットダウンの猶予時間 (`SHUTDOWN_TIMEOUT_SECONDS`) を過ぎても終わらなかったリクエストを警告として残す。
+/// この後プロセスは強制終了するので、これらのリクエストには応答が返らない。
+fn log_unfinished_requests(in_flight: &InFlightRequests, timeout: Duration) {
+    let unfinished = in_flight.snapshot();
+    warn!(
+        "Graceful shutdown timed out after {}s with {} request(s) still in flight; forcing exit",
+        timeout.as_secs(),
+        unfinished.len()
+    );
+    for request in unfinished {
+        warn!(
+            "Abandoned in-flight request: {} {} (running for {} ms)",
+            request.method,
+            request.path,
+            request.elapsed().as_millis()
+        );
+    }
+}
+
 /// ルーターと共有ステート・ミドルウェアをまとめて生成する。
-/// `Router::new()` に対して `route` をチェーンし、最後に `with_state` で `Arc<Database>`
-/// を渡すことで、各ハンドラが `State<Arc<Database>>` から DB にアクセスできる。
-fn create_router(database: Arc<Database>) -> Router {
-    Router::new()
+/// `Router::new()` に対して `route` をチェーンし、最後に `with_state` で `AppState`
+/// を渡すことで、各ハンドラが `State<Arc<Database>>`/`State<Arc<Config>>` を取り出せる。
+fn create_router(state: AppState) -> Router {
+    let cors = create_cors_layer(&state.config.cors_allowed_origins, &state.config.environment);
+    let request_timeout = state.config.request_timeout;
+    let max_body_bytes = state.config.max_body_bytes;
+    let body_logging = BodyLogging { enabled: state.config.log_bodies, max_bytes: BODY_LOG_MAX_BYTES };
+
+    let mut router = Router::new()
         // Health check endpoint
         .route("/health", get(health_check))
+        .route("/health/ready", get(readiness_check))
         // User management endpoints
         .route("/api/users", post(create_user))
         .route("/api/users", get(get_all_users))
+        .route("/api/users/search", get(search_users))
+        .route("/api/users/with-posts", post(create_user_with_posts))
         .route("/api/users/:id", get(get_user_by_id))
         .route("/api/users/:id", put(update_user))
         .route("/api/users/:id", delete(delete_user))
+        .route("/api/users/:id/posts", get(get_user_posts))
+        .route("/api/users/:id/transfer-posts/:to_id", post(transfer_posts))
+        .route("/api/users/:id/verify-email", post(verify_user_email))
         // Post management endpoints
         .route("/api/posts", post(create_post))
         .route("/api/posts", get(get_all_posts))
+        .route("/api/posts/on-this-day", get(get_posts_on_this_day))
         .route("/api/posts/:id", get(get_post_by_id))
+        .route("/api/posts/:id", put(update_post))
+        .route("/api/posts/:id/author", get(get_post_author))
+        // Search across users, posts and vocabulary
+        .route("/api/search", get(search))
         // Vocabulary management endpoints
         .route("/api/vocabulary", post(create_vocabulary))
         .route("/api/vocabulary", get(get_all_vocabulary))
         .route("/api/vocabulary/random", get(get_random_vocabulary))
+        .route("/api/vocabulary/tags", get(get_vocabulary_tags))
+        .route("/api/vocabulary/index", get(get_vocabulary_index))
+        .route("/api/vocabulary/changes", get(get_vocabulary_changes))
+        .route("/api/vocabulary/stream", get(stream_vocabulary))
+        .route("/api/vocabulary/events", get(vocabulary_events))
+        .route("/api/vocabulary/coverage", post(get_vocabulary_coverage))
+        .route("/api/vocabulary/batch", get(get_vocabulary_batch))
+        .route("/api/vocabulary/due", get(get_due_vocabulary))
         .route("/api/vocabulary/:id", get(get_vocabulary_by_id))
-        // Add shared state (database connection)
-        .with_state(database)
+        .route("/api/vocabulary/:id", patch(update_vocabulary))
+        .route("/api/vocabulary/:id/examples", get(get_vocabulary_examples))
+        .route("/api/vocabulary/:id/history", get(get_vocabulary_history))
+        .route("/api/vocabulary/:id/review", post(review_vocabulary))
+        .route("/api/vocabulary/:id/favorite", post(set_vocabulary_favorite))
+        .route("/api/vocabulary/:id/favorited-by", get(get_vocabulary_favorited_by))
+        .route("/api/vocabulary/:id/related", get(get_related_vocabulary))
+        // Admin endpoints
+        .route("/api/admin/vocabulary/auto-difficulty", post(auto_assign_vocabulary_difficulty))
+        // OpenAPI spec and Swagger UI
+        .merge(SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_JSON_PATH, api_doc(&state.config.environment)));
+
+    // Wiping data and reading the audit trail are only for local development; in production the routes don't exist at all
+    if state.config.environment.is_local() {
+        router = router
+            .route("/api/admin/vocabulary/reset", post(reset_vocabulary))
+            // No authentication yet, so the audit trail (emails included) stays off production
+            .route("/api/admin/audit", get(get_audit_log));
+    }
+
+    // Build and runtime info for ops (can be hidden with INFO_ENDPOINT_ENABLED=false)
+    if state.config.info_endpoint_enabled {
+        router = router.route("/info", get(build_info));
+    }
+
+    // JSON errors for unknown paths and unsupported methods (405 keeps axum's Allow header);
+    // the 405 fallback only reaches routes that already exist, so it has to come after all of them
+    router = router
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed);
+
+    let in_flight = state.in_flight.clone();
+    router
+        // Debug-level body logging; passes requests straight through unless LOG_BODIES is on in local
+        .layer(from_fn_with_state(body_logging, log_bodies))
+        // Track running requests so a timed-out shutdown can report them
+        .layer(from_fn_with_state(in_flight, track_in_flight))
+        // Add shared state (database connection and configuration)
+        .with_state(state)
         // Apply middleware stack
-        .layer(create_middleware_stack())
+        .layer(create_middleware_stack(cors, request_timeout, max_body_bytes))
+}
+
+/// `--check-db` 用。HTTP リスナーを立てずに、接続文字列のパース・プール作成・ヘルスチェックだけを行う。
+/// 引数で URL が渡されればそれを、なければ環境変数 (`DATABASE_URL` または `DATABASE_*`) を使う。
+/// CI/CD からの利用を想定し、成功なら 0、失敗なら 1 を終了コードとして返す。
+async fn check_database(connection_string: Option<&str>) -> i32 {
+    let database_config = match connection_string {
+        Some(url) => DatabaseConfig::from_connection_string(url),
+        None => {
+            // Config::from_env normally loads .env; do the same for this path
+            dotenvy::dotenv().ok();
+            DatabaseConfig::from_env()
+        }
+    };
+
+    let database_config = match database_config {
+        Ok(database_config) => database_config,
+        Err(e) => {
+            eprintln!("Database check failed: invalid configuration: {:#}", e);
+            return 1;
+        }
+    };
+
+    let connection_timeout = database_config.connection_timeout;
+    let database = match Database::new(database_config).await {
+        Ok(database) => database,
+        Err(e) => {
+            eprintln!("Database check failed: could not connect: {}", e);
+            return 1;
+        }
+    };
+
+    if let Err(e) = with_startup_timeout(connection_timeout, "Database health check", database.health_check()).await {
+        eprintln!("Database check failed: health check error: {}", e);
+        return 1;
+    }
+
+    println!("Database check passed");
+    0
+}
+
+/// 語彙の変更イベントを語彙キャッシュに反映する。他インスタンスの書き込みで古くなった項目を捨てるための購読者。
+async fn apply_vocabulary_changes(database: Arc<Database>, mut changes: broadcast::Receiver<VocabularyChange>) {
+    loop {
+        match changes.recv().await {
+            Ok(change) => {
+                debug!("Vocabulary changed: {:?}", change);
+                database.apply_vocabulary_change(change);
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                // We can't tell which entries the skipped events were about
+                warn!("Missed {} vocabulary change events, clearing the vocabulary cache", skipped);
+                database.apply_vocabulary_change(VocabularyChange::Resync);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// `REVIEW_WRITE_BEHIND` で溜めた復習結果を、間隔ごとか件数が上限に達したときに書き出し続ける。
+/// 失敗した分はバッファに残るので、警告だけ出して次の書き出しで再送する。
+async fn flush_reviews_periodically(database: Arc<Database>) {
+    let Some(interval) = database.review_buffer().map(|reviews| reviews.flush_interval()) else {
+        return;
+    };
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        let filled = async {
+            if let Some(reviews) = database.review_buffer() {
+                reviews.filled().await;
+            }
+        };
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = filled => {}
+        }
+
+        if let Err(e) = database.flush_reviews().await {
+            let pending = database.review_buffer().map_or(0, |reviews| reviews.len());
+            warn!("Failed to flush buffered reviews ({} pending, will retry): {}", pending, e);
+        }
+    }
+}
+
+/// シャットダウン前に、溜まっている復習結果を書き出す。失敗した分は失われるのでエラーとして残す。
+async fn flush_buffered_reviews(database: &Database) {
+    match database.flush_reviews().await {
+        Ok(0) => {}
+        Ok(flushed) => info!("Flushed {} buffered reviews before shutdown", flushed),
+        Err(e) => {
+            let pending = database.review_buffer().map_or(0, |reviews| reviews.len());
+            error!("Failed to flush {} buffered reviews before shutdown; they are lost: {}", pending, e);
+        }
+    }
+}
+
+/// 猶予時間を過ぎて強制終了する前に、溜まっている復習結果を書き出してプールを閉じる。
+/// 終わらないリクエストが接続を握ったままのこともあるので `FORCED_EXIT_CLEANUP_TIMEOUT` で打ち切り、失われる件数を残す。
+async fn cleanup_before_forced_exit(database: &Database) {
+    let cleanup = async {
+        flush_buffered_reviews(database).await;
+        database.close().await;
+    };
+    if tokio::time::timeout(FORCED_EXIT_CLEANUP_TIMEOUT, cleanup).await.is_err() {
+        let pending = database.review_buffer().map_or(0, |reviews| reviews.len());
+        error!(
+            "Shutdown cleanup did not finish within {:?}; {} buffered reviews are lost",
+            FORCED_EXIT_CLEANUP_TIMEOUT, pending
+        );
+    }
 }
 
 /// グレースフルシャットダウンを司るシグナル待ちハンドラ。
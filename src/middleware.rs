@@ -1,33 +1,72 @@
-use axum::http::Method;
-use std::time::Duration;
-use tower::ServiceBuilder;
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    error_handling::HandleErrorLayer,
+    extract::{DefaultBodyLimit, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::{map_response, MapResponseLayer, Next},
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use serde_json::Value;
+use std::{future::Ready, time::Duration};
+use tower::{
+    layer::util::{Identity, Stack},
+    timeout::{error::Elapsed, TimeoutLayer},
+    ServiceBuilder,
+};
 use tower_http::{
+    classify::{ServerErrorsAsFailures, SharedClassifier},
     cors::{Any, CorsLayer},
-    timeout::TimeoutLayer,
     trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
 };
-use tracing::Level;
+use tracing::{debug, warn, Level};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-/// アプリ全体で使う Tower ミドルウェアを積み上げて返す。
-/// `ServiceBuilder` を返すことで `Router::layer` にそのまま差し込める。
-pub fn create_middleware_stack() -> ServiceBuilder<
-    tower::layer::util::Stack<
-        TimeoutLayer,
-        tower::layer::util::Stack<
-            CorsLayer,
-            tower::layer::util::Stack<
-                TraceLayer<
-                    tower_http::classify::SharedClassifier<tower_http::classify::ServerErrorsAsFailures>,
-                    DefaultMakeSpan,
-                    DefaultOnRequest,
-                    DefaultOnResponse,
+use crate::{config::{Environment, LogFormat}, error::ApiError, handlers::SESSION_ID_HEADER};
+
+/// ミドルウェア層で発生したエラーを `ApiError` に変換する関数の型。
+/// クロージャではなく関数ポインタにしておくことで、`MiddlewareStack` として型に名前を付けられる。
+type MiddlewareErrorHandler = fn(BoxError) -> Ready<ApiError>;
+
+/// レスポンスを書き換える関数の型。`MiddlewareErrorHandler` と同じく、型に名前を付けるため関数ポインタにしている。
+type ResponseMapper = fn(Response) -> Ready<Response>;
+
+/// `create_middleware_stack` が返すレイヤー群の型。
+/// 外側から Trace → CORS → エラー変換 → タイムアウト → 413 の変換 → ボディ上限の順に積まれる。
+pub type MiddlewareStack = ServiceBuilder<
+    Stack<
+        DefaultBodyLimit,
+        Stack<
+            MapResponseLayer<ResponseMapper, (), ()>,
+            Stack<
+                TimeoutLayer,
+                Stack<
+                    HandleErrorLayer<MiddlewareErrorHandler, ()>,
+                    Stack<
+                        CorsLayer,
+                        Stack<
+                            TraceLayer<
+                                SharedClassifier<ServerErrorsAsFailures>,
+                                DefaultMakeSpan,
+                                DefaultOnRequest,
+                                DefaultOnResponse,
+                            >,
+                            Identity,
+                        >,
+                    >,
                 >,
-                tower::layer::util::Identity,
             >,
         >,
     >,
-> {
+>;
+
+/// アプリ全体で使う Tower ミドルウェアを積み上げて返す。
+/// `ServiceBuilder` を返すことで `Router::layer` にそのまま差し込める。
+/// CORS レイヤーは設定に依存するため、`create_cors_layer` で作ったものを受け取る。
+/// `timeout` はリクエスト全体の上限 (`REQUEST_TIMEOUT_SECONDS`) で、DB の接続タイムアウトとは別物。
+/// `max_body_bytes` は全ルート共通のボディ上限 (`MAX_BODY_BYTES`)。一括インポートのように大きな
+/// ボディを受けるルートは、`post(handler).layer(DefaultBodyLimit::max(n))` で個別に上書きできる。
+pub fn create_middleware_stack(cors: CorsLayer, timeout: Duration, max_body_bytes: usize) -> MiddlewareStack {
     ServiceBuilder::new()
         // Request/response logging with tracing
         .layer(
@@ -37,48 +76,523 @@ pub fn create_middleware_stack() -> ServiceBuilder<
                 .on_response(DefaultOnResponse::new().level(Level::INFO)),
         )
         // CORS configuration for cross-origin requests
-        .layer(create_cors_layer())
-        // Request timeout handling (30 seconds)
-        .layer(TimeoutLayer::new(Duration::from_secs(30)))
+        .layer(cors)
+        // Convert timeout errors into the standard JSON error envelope
+        .layer(HandleErrorLayer::new(handle_middleware_error as MiddlewareErrorHandler))
+        // Request timeout handling
+        .layer(TimeoutLayer::new(timeout))
+        // Axum's own 413 is plain text; rewrite it into the standard JSON error envelope
+        .layer(map_response(payload_too_large_to_api_error as ResponseMapper))
+        // Default body size limit; a route-level DefaultBodyLimit applied later takes precedence
+        .layer(DefaultBodyLimit::max(max_body_bytes))
+}
+
+/// ボディ上限を超えたときに `Json` などの抽出器が返すプレーンテキストの 413 を、
+/// `ApiError::PayloadTooLarge` の JSON レスポンスに置き換える。それ以外のレスポンスはそのまま返す。
+fn payload_too_large_to_api_error(response: Response) -> Ready<Response> {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+
+    let response = if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        ApiError::PayloadTooLarge.into_response()
+    } else {
+        response
+    };
+    std::future::ready(response)
+}
+
+/// `TimeoutLayer` などが返す `BoxError` を `ApiError` に読み替える。
+/// タイムアウトは 408 (`TIMEOUT`)、それ以外は想定外のエラーとして 500 にする。
+fn handle_middleware_error(err: BoxError) -> Ready<ApiError> {
+    let api_error = if err.is::<Elapsed>() {
+        ApiError::Timeout
+    } else {
+        ApiError::Internal(anyhow::anyhow!("Unhandled middleware error: {}", err))
+    };
+    std::future::ready(api_error)
+}
+
+/// `LOG_BODIES` で記録するボディの最大バイト数。超えた分は省略する。
+pub const BODY_LOG_MAX_BYTES: usize = 4096;
+
+/// ボディのログで値を伏せる JSON のキー。大文字小文字を無視した部分一致で判定する (`new_password` なども伏せる)。
+const REDACTED_BODY_KEYS: &[&str] = &["password", "secret", "token", "authorization", "api_key"];
+
+/// `log_bodies` ミドルウェアの設定。
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLogging {
+    /// ローカル環境かつ `LOG_BODIES=true` のときだけ `true`
+    pub enabled: bool,
+    /// 1 つのボディにつき記録する最大バイト数
+    pub max_bytes: usize,
+}
+
+/// ローカルでのデバッグ用に、リクエストとレスポンスのボディを debug レベルでログに出すミドルウェア。
+/// ボディを丸ごとメモリに読み込むため、無効なときは何もせずに次へ渡す。
+/// SSE など長さの決まらないレスポンスは読み切れないので、記録せずにそのまま流す。
+/// JSON ならパスワードなどの値を伏せてから、`max_bytes` で切り詰めて記録する。
+pub async fn log_bodies(State(logging): State<BodyLogging>, request: Request, next: Next) -> Response {
+    if !logging.enabled {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let (parts, body) = request.into_parts();
+    // The route's DefaultBodyLimit still applies: its extension stays in parts for the extractor
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return ApiError::Validation(format!("Failed to read request body: {}", e)).into_response(),
+    };
+    debug!("Request body {} {}: {}", method, path, body_for_log(&bytes, logging.max_bytes));
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    if is_streamed(&response) {
+        debug!("Response body {} {} ({}): (streamed, not logged)", method, path, response.status().as_u16());
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to read response body of {} {} for logging: {}", method, path, e);
+            return ApiError::Internal(anyhow::anyhow!("Failed to read response body: {}", e)).into_response();
+        }
+    };
+    debug!(
+        "Response body {} {} ({}): {}",
+        method,
+        path,
+        parts.status.as_u16(),
+        body_for_log(&bytes, logging.max_bytes)
+    );
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// SSE (`text/event-stream`) か、`Content-Length` も長さのヒントもないストリーミングのレスポンスか。
+/// これらを読み切ろうとすると、SSE は応答が返らなくなり、ストリーミングの JSON は一括で返ることになる。
+fn is_streamed(response: &Response) -> bool {
+    let event_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    let unknown_length = !response.headers().contains_key(header::CONTENT_LENGTH)
+        && response.body().size_hint().exact().is_none();
+    event_stream || unknown_length
+}
+
+/// ログに出す形にボディを整える。JSON なら秘密の値を伏せ、それ以外は UTF-8 として (不正なバイトは置き換えて) 出す。
+fn body_for_log(bytes: &[u8], max_bytes: usize) -> String {
+    if bytes.is_empty() {
+        return "(empty)".to_string();
+    }
+
+    let text = match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut value) => {
+            redact_secrets(&mut value);
+            value.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    };
+
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} more bytes)", &text[..end], text.len() - end)
+}
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                let key = key.to_lowercase();
+                if REDACTED_BODY_KEYS.iter().any(|secret| key.contains(secret)) {
+                    *value = Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
 }
 
-/// CORS を緩めに許可するレイヤー。
-/// `CorsLayer::new()` からビルダー的に `allow_origin` などをチェーンして設定する。
-fn create_cors_layer() -> CorsLayer {
-    CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([
-            Method::GET,
-            Method::POST,
-            Method::PUT,
-            Method::DELETE,
-            Method::OPTIONS,
-        ])
-        .allow_headers(Any)
-        .allow_credentials(false)
+/// 許可するオリジンに応じた CORS レイヤーを作る。
+/// `CORS_ALLOWED_ORIGINS` が設定されていればそのオリジンだけを許可し、Cookie などの資格情報付き
+/// リクエストも受け付ける。未設定の場合、`Any` を許すのはローカル環境だけで、本番ではクロスオリジンを許可しない。
+pub fn create_cors_layer(allowed_origins: &[String], environment: &Environment) -> CorsLayer {
+    let cors = CorsLayer::new().allow_methods([
+        Method::GET,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+        Method::OPTIONS,
+    ]);
+
+    if !allowed_origins.is_empty() {
+        // Origins were validated in Config, so conversion only fails on a programming error
+        let origins: Vec<HeaderValue> = allowed_origins
+            .iter()
+            .filter_map(|origin| HeaderValue::from_str(origin).ok())
+            .collect();
+
+        // Credentials can't be combined with wildcard headers, so list them explicitly
+        return cors
+            .allow_origin(origins)
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::ACCEPT,
+                SESSION_ID_HEADER,
+            ])
+            .allow_credentials(true);
+    }
+
+    match environment {
+        Environment::Local => cors
+            .allow_origin(Any)
+            .allow_headers(Any)
+            .allow_credentials(false),
+        Environment::Production => {
+            warn!("CORS_ALLOWED_ORIGINS is not set; cross-origin requests will be rejected");
+            cors
+        }
+    }
 }
 
-/// Tracing サブスクライバを JSON ログ出力に設定する。
-/// `EnvFilter` により `RUST_LOG=debug` のような環境変数制御も可能。
+/// `APP_LOG_LEVEL` に指定できるレベル。
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error", "off"];
+
+/// `RUST_LOG` がないときの `EnvFilter` ディレクティブを組み立てる。
+/// このクレートのログは `app_level`、リクエストログを出す `tower_http` は `info`、
+/// それ以外の依存クレート (hyper・tokio_postgres など) は `warn` に抑える。
+pub fn log_filter_directives(app_level: &str) -> Result<String, String> {
+    let app_level = app_level.trim().to_lowercase();
+    if !LOG_LEVELS.contains(&app_level.as_str()) {
+        return Err(format!(
+            "APP_LOG_LEVEL must be one of {} (got '{}')",
+            LOG_LEVELS.join(", "),
+            app_level
+        ));
+    }
+
+    Ok(format!("warn,{}={},tower_http=info", env!("CARGO_CRATE_NAME"), app_level))
+}
+
+/// Tracing サブスクライバを初期化する。形式は `LOG_FORMAT` (`json`/`pretty`/`compact`) で選べ、
+/// 未指定ならローカルは読みやすい `pretty`、本番は構造化された `json` になる。
+/// `RUST_LOG` があればそのまま `EnvFilter` に渡す (例: `word_rest_api::db=debug,info`)。
+/// なければ `APP_LOG_LEVEL` (既定 `info`) から `log_filter_directives` でフィルタを作る。
 pub fn init_tracing() -> Result<(), Box<dyn std::error::Error>> {
-    // Create environment filter for log levels
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info"));
-
-    // Initialize tracing subscriber with JSON formatting
-    tracing_subscriber::registry()
-        .with(env_filter)
-        .with(
-            tracing_subscriber::fmt::layer()
-                .json()
-                .with_current_span(false)
-                .with_span_list(true)
-                .with_target(true)
-                .with_thread_ids(true)
-                .with_thread_names(true)
-        )
-        .try_init()?;
+    // Runs before Config::from_env, so load .env here for LOG_FORMAT, ENV and RUST_LOG
+    dotenvy::dotenv().ok();
+    let log_format = LogFormat::from_env()?;
+
+    // RUST_LOG keeps full control; otherwise only this crate follows APP_LOG_LEVEL
+    let env_filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => {
+            let app_level = std::env::var("APP_LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+            EnvFilter::try_new(log_filter_directives(&app_level)?)?
+        }
+    };
+
+    let registry = tracing_subscriber::registry().with(env_filter);
+    match log_format {
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(false)
+                    .with_span_list(true)
+                    .with_target(true)
+                    .with_thread_ids(true)
+                    .with_thread_names(true)
+            )
+            .try_init()?,
+        LogFormat::Pretty => registry
+            .with(tracing_subscriber::fmt::layer().pretty().with_target(true))
+            .try_init()?,
+        LogFormat::Compact => registry
+            .with(tracing_subscriber::fmt::layer().compact().with_target(true))
+            .try_init()?,
+    }
 
-    tracing::info!("Structured logging initialized with JSON format");
+    tracing::info!("Logging initialized with {} format", log_format.as_str());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+        body::HttpBody,
+        routing::{get, post},
+        Json, Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::{config::DEFAULT_MAX_BODY_BYTES, extract::ApiJson};
+
+    async fn slow_handler() -> &'static str {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        "done"
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_returns_json_envelope() {
+        let app = Router::new()
+            .route("/slow", get(slow_handler))
+            .layer(create_middleware_stack(
+                create_cors_layer(&[], &Environment::Local),
+                Duration::from_secs(5),
+                DEFAULT_MAX_BODY_BYTES,
+            ));
+
+        let response = app
+            .oneshot(Request::builder().uri("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "TIMEOUT");
+        assert_eq!(json["error"]["message"], "Request timed out");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_request_within_configured_timeout_succeeds() {
+        let app = Router::new()
+            .route("/slow", get(slow_handler))
+            .layer(create_middleware_stack(
+                create_cors_layer(&[], &Environment::Local),
+                Duration::from_secs(120),
+                DEFAULT_MAX_BODY_BYTES,
+            ));
+
+        let response = app
+            .oneshot(Request::builder().uri("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    async fn echo_len(Json(body): Json<serde_json::Value>) -> String {
+        body.to_string().len().to_string()
+    }
+
+    async fn post_json(app: Router, uri: &str, size: usize) -> axum::http::Response<Body> {
+        let body = serde_json::json!({ "text": "a".repeat(size) }).to_string();
+        app.oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn body_limit_app() -> Router {
+        Router::new()
+            .route("/small", post(echo_len))
+            .route("/bulk", post(echo_len).layer(DefaultBodyLimit::max(64 * 1024)))
+            .layer(create_middleware_stack(
+                create_cors_layer(&[], &Environment::Local),
+                Duration::from_secs(30),
+                1024,
+            ))
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_returns_json_envelope() {
+        let response = post_json(body_limit_app(), "/small", 4096).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(json["error"]["message"], "Request body is too large");
+    }
+
+    #[tokio::test]
+    async fn test_route_can_raise_body_limit() {
+        let small = post_json(body_limit_app(), "/small", 100).await;
+        assert_eq!(small.status(), StatusCode::OK);
+
+        let bulk = post_json(body_limit_app(), "/bulk", 4096).await;
+        assert_eq!(bulk.status(), StatusCode::OK);
+
+        let too_big = post_json(body_limit_app(), "/bulk", 128 * 1024).await;
+        assert_eq!(too_big.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    async fn preflight(cors: CorsLayer, origin: &str) -> axum::http::Response<Body> {
+        let app = Router::new()
+            .route(
+                "/api/users",
+                get(|| async { "ok" }).post(|ApiJson(_): ApiJson<serde_json::Value>| async { "created" }),
+            )
+            .layer(create_middleware_stack(cors, Duration::from_secs(30), DEFAULT_MAX_BODY_BYTES));
+
+        app.oneshot(
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/api/users")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cors_allows_configured_origin_with_credentials() {
+        let origins = vec!["https://app.example.com".to_string()];
+        let cors = create_cors_layer(&origins, &Environment::Production);
+
+        let response = preflight(cors, "https://app.example.com").await;
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    }
+
+    #[tokio::test]
+    async fn test_cors_rejects_unlisted_origin() {
+        let origins = vec!["https://app.example.com".to_string()];
+        let cors = create_cors_layer(&origins, &Environment::Production);
+
+        let response = preflight(cors, "https://evil.example.com").await;
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cors_falls_back_to_any_only_in_local() {
+        let local = preflight(create_cors_layer(&[], &Environment::Local), "https://any.example.com").await;
+        assert_eq!(local.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+
+        let production = preflight(create_cors_layer(&[], &Environment::Production), "https://any.example.com").await;
+        assert!(production.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_is_not_subject_to_json_content_type() {
+        // Preflights carry no body or Content-Type; CORS answers them before any JSON extractor runs
+        let response = preflight(create_cors_layer(&[], &Environment::Local), "https://any.example.com").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// Echoes whether the request body arrived with a known length, as a streamed response. A streamed
+    /// body only gets one once something buffered it, so this shows whether log_bodies touched each.
+    async fn size_hints(body: Body) -> Body {
+        let request_len = body.size_hint().exact();
+        Body::from_stream(futures_util::stream::iter([Ok::<_, std::io::Error>(format!("{:?}", request_len))]))
+    }
+
+    async fn call_with_body_logging(enabled: bool) -> (Option<u64>, String) {
+        let app = Router::new()
+            .route("/echo", post(size_hints))
+            .layer(axum::middleware::from_fn_with_state(
+                BodyLogging { enabled, max_bytes: BODY_LOG_MAX_BYTES },
+                log_bodies,
+            ));
+        let request_body = Body::from_stream(futures_util::stream::iter([Ok::<_, std::io::Error>(r#"{"name":"a"}"#)]));
+
+        let response = app
+            .oneshot(Request::builder().method("POST").uri("/echo").body(request_body).unwrap())
+            .await
+            .unwrap();
+        let response_len = response.body().size_hint().exact();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (response_len, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_body_logging_is_a_no_op_when_disabled() {
+        let (response_len, request_len) = call_with_body_logging(false).await;
+        assert_eq!(response_len, None);
+        assert_eq!(request_len, "None");
+
+        // The request is buffered for logging; the streamed response passes through untouched
+        let (response_len, request_len) = call_with_body_logging(true).await;
+        assert_eq!(response_len, None);
+        assert_eq!(request_len, "Some(12)");
+    }
+
+    #[tokio::test]
+    async fn test_sse_events_still_arrive_with_body_logging_on() {
+        use axum::response::sse::{Event, Sse};
+        use futures_util::StreamExt;
+
+        async fn events() -> Sse<impl futures_util::Stream<Item = Result<Event, std::convert::Infallible>>> {
+            // Like a real event stream, it never ends on its own
+            let first = futures_util::stream::iter([Ok(Event::default().data("hello"))]);
+            Sse::new(first.chain(futures_util::stream::pending()))
+        }
+
+        let app = Router::new()
+            .route("/events", get(events))
+            .layer(axum::middleware::from_fn_with_state(
+                BodyLogging { enabled: true, max_bytes: BODY_LOG_MAX_BYTES },
+                log_bodies,
+            ));
+
+        let first_event = async {
+            let response = app
+                .oneshot(Request::builder().uri("/events").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            response.into_body().into_data_stream().next().await
+        };
+        let event = tokio::time::timeout(Duration::from_secs(2), first_event)
+            .await
+            .expect("event was held back by body logging")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&event[..], b"data: hello\n\n");
+    }
+
+    #[test]
+    fn test_body_log_redacts_secrets_and_truncates() {
+        let body = br#"{"name":"a","password":"hunter2","nested":[{"API_KEY":"k"}]}"#;
+        assert_eq!(
+            body_for_log(body, BODY_LOG_MAX_BYTES),
+            r#"{"name":"a","nested":[{"API_KEY":"[REDACTED]"}],"password":"[REDACTED]"}"#
+        );
+
+        // Cut on a character boundary, never inside a multi-byte character
+        assert_eq!(body_for_log("りんごりんご".as_bytes(), 4), "り... (15 more bytes)");
+        assert_eq!(body_for_log(b"", 4), "(empty)");
+    }
+
+    #[test]
+    fn test_app_log_level_only_applies_to_this_crate() {
+        let directives = log_filter_directives(" DEBUG ").unwrap();
+        assert_eq!(directives, "warn,word_rest_api=debug,tower_http=info");
+        assert!(EnvFilter::try_new(&directives).is_ok());
+
+        assert!(log_filter_directives("verbose").is_err());
+    }
+}
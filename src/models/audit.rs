@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+/// 監査ログの `entity_type`。今はユーザーの変更だけを記録する。
+pub const AUDIT_ENTITY_USER: &str = "user";
+
+/// 監査ログに記録する操作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    /// `audit_log.action` に入れる文字列。
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+/// `audit_log` の 1 行。追記専用で、アプリから更新・削除することはない。
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// 対象の種別 (`user`)
+    pub entity_type: String,
+    /// 対象の ID (ユーザーなら UUID)
+    pub entity_id: String,
+    /// `create`・`update`・`delete` のいずれか
+    pub action: String,
+    /// 作成時は作成したユーザー、更新時は `{ "changes": {...} }`、削除時は `{ "force": bool }`
+    #[schema(value_type = Object)]
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// `GET /api/admin/audit` のクエリパラメータ。`limit`/`offset` は `Pagination` 抽出器が読む。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogParams {
+    /// この ID の対象の記録だけを返す。省略時は全件
+    pub entity_id: Option<String>,
+}
+
+impl AuditLogParams {
+    /// 前後の空白を除いた `entity_id`。省略・空なら `None`。
+    pub fn entity_id(&self) -> Option<&str> {
+        self.entity_id.as_deref().map(str::trim).filter(|id| !id.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blank_entity_id_reads_everything() {
+        let params = AuditLogParams::default();
+        assert_eq!(params.entity_id(), None);
+
+        let params = AuditLogParams { entity_id: Some("  ".to_string()) };
+        assert_eq!(params.entity_id(), None);
+
+        let params = AuditLogParams { entity_id: Some(" 5f1c0f4e-3a1b-4d5e-9c7a-2b8e6d4f1a3c ".to_string()) };
+        assert_eq!(params.entity_id(), Some("5f1c0f4e-3a1b-4d5e-9c7a-2b8e6d4f1a3c"));
+    }
+
+    #[test]
+    fn test_actions_are_stored_lowercase() {
+        let actions: Vec<&str> = [AuditAction::Create, AuditAction::Update, AuditAction::Delete]
+            .iter()
+            .map(AuditAction::as_str)
+            .collect();
+        assert_eq!(actions, vec!["create", "update", "delete"]);
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::cell::Cell;
+use utoipa::IntoParams;
+
+/// `?fields=id,en_word` で指定された、レスポンスに残すフィールド (スパースフィールドセット)。
+/// 通信量を減らしたいモバイルクライアント向け。`None` なら全フィールドを返す。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldSelection(Option<Vec<String>>);
+
+impl FieldSelection {
+    /// カンマ区切りの `fields` を解釈する。`allowed` にない名前があればエラー。
+    /// 省略・空なら全フィールドを返す選択になる。重複や前後の空白は無視する。
+    pub fn parse(fields: Option<&str>, allowed: &[&str]) -> Result<Self, String> {
+        let Some(fields) = fields.map(str::trim).filter(|fields| !fields.is_empty()) else {
+            return Ok(Self(None));
+        };
+
+        let mut selected: Vec<String> = Vec::new();
+        for field in fields.split(',').map(str::trim).filter(|field| !field.is_empty()) {
+            if !allowed.contains(&field) {
+                return Err(format!(
+                    "Unknown field '{}' in 'fields'. Must be one of: {}",
+                    field,
+                    allowed.join(", ")
+                ));
+            }
+            if !selected.iter().any(|existing| existing == field) {
+                selected.push(field.to_string());
+            }
+        }
+
+        if selected.is_empty() {
+            return Ok(Self(None));
+        }
+        Ok(Self(Some(selected)))
+    }
+
+    /// 全フィールドを返す (絞り込みなし) かどうか。
+    pub fn is_all(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// `value` を JSON にし、選ばれたフィールドだけを残す。オブジェクト以外はそのまま返す。
+    pub fn project<T: Serialize>(&self, value: &T) -> Result<Value, serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        let Some(fields) = &self.0 else {
+            return Ok(value);
+        };
+
+        match value {
+            Value::Object(mut object) => {
+                let projected: Map<String, Value> = fields
+                    .iter()
+                    .filter_map(|field| object.remove(field).map(|value| (field.clone(), value)))
+                    .collect();
+                Ok(Value::Object(projected))
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// `project` を各要素に適用する。ページングとは独立で、取得済みのページをそのまま射影する。
+    pub fn project_all<T: Serialize>(&self, values: &[T]) -> Result<Vec<Value>, serde_json::Error> {
+        values.iter().map(|value| self.project(value)).collect()
+    }
+}
+
+/// JSON での `id` の型 (`?id_as_string=true` で文字列)。既定は数値のまま。
+/// ID を不透明な文字列キーとして扱うクライアント (TypeScript の `id: string` なモデルや GraphQL の `ID` 型、
+/// 将来 64 ビット ID になっても JavaScript の `Number` で桁落ちさせたくないクライアント) 向け。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdFormat {
+    #[default]
+    Number,
+    String,
+}
+
+impl IdFormat {
+    /// `id_as_string` クエリパラメータから決める。
+    pub fn from_flag(id_as_string: bool) -> Self {
+        if id_as_string {
+            IdFormat::String
+        } else {
+            IdFormat::Number
+        }
+    }
+
+    /// `f` の中で行うシリアライズの間だけ、`serialize_id` にこの形式を使わせる。抜けるときに元の形式へ戻す。
+    fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        struct Restore(IdFormat);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_ID_FORMAT.with(|current| current.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CURRENT_ID_FORMAT.with(|current| current.replace(self)));
+        f()
+    }
+}
+
+thread_local! {
+    // Serialization is synchronous, so a per-thread value is enough to carry the choice down to the id fields
+    static CURRENT_ID_FORMAT: Cell<IdFormat> = const { Cell::new(IdFormat::Number) };
+}
+
+/// 語彙 ID のフィールドに `#[serde(serialize_with = "serialize_id")]` で付ける。
+/// 普段は数値のまま書き、`WithIdFormat` で `IdFormat::String` を選んだシリアライズの中では文字列にする。
+pub fn serialize_id<S: Serializer>(id: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+    match CURRENT_ID_FORMAT.with(Cell::get) {
+        IdFormat::Number => serializer.serialize_i32(*id),
+        IdFormat::String => serializer.collect_str(id),
+    }
+}
+
+/// `serialize_id` の配列版 (`missing_ids` など)。
+pub fn serialize_ids<S: Serializer>(ids: &[i32], serializer: S) -> Result<S::Ok, S::Error> {
+    struct Id(i32);
+    impl Serialize for Id {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_id(&self.0, serializer)
+        }
+    }
+
+    serializer.collect_seq(ids.iter().map(|id| Id(*id)))
+}
+
+/// 値を選んだ `IdFormat` でシリアライズするラッパー。`Json(WithIdFormat(vocabulary, id_format))` のように
+/// レスポンスごとに包む。中の型は ID のフィールドに `serialize_id` を付けておくこと。
+#[derive(Debug, Clone, Copy)]
+pub struct WithIdFormat<T>(pub T, pub IdFormat);
+
+impl<T: Serialize> Serialize for WithIdFormat<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.1.scope(|| self.0.serialize(serializer))
+    }
+}
+
+/// `?id_as_string=true` だけを受け取るクエリ。語彙を返すエンドポイントで、他のクエリと並べて使う。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IdFormatQuery {
+    /// `true` なら語彙の `id` を JSON の文字列で返す
+    #[serde(default)]
+    pub id_as_string: bool,
+}
+
+impl IdFormatQuery {
+    /// `id_as_string` に対応する `id` の型。
+    pub fn id_format(&self) -> IdFormat {
+        IdFormat::from_flag(self.id_as_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALLOWED: &[&str] = &["id", "en_word", "ja_word", "tags"];
+
+    #[derive(Serialize)]
+    struct Word {
+        id: i32,
+        en_word: &'static str,
+        ja_word: &'static str,
+        tags: Vec<&'static str>,
+    }
+
+    fn word() -> Word {
+        Word { id: 1, en_word: "apple", ja_word: "りんご", tags: vec!["food"] }
+    }
+
+    #[test]
+    fn test_missing_or_blank_fields_select_everything() {
+        assert!(FieldSelection::parse(None, ALLOWED).unwrap().is_all());
+        assert!(FieldSelection::parse(Some(" "), ALLOWED).unwrap().is_all());
+        assert!(FieldSelection::parse(Some(",,"), ALLOWED).unwrap().is_all());
+
+        let all = FieldSelection::default().project(&word()).unwrap();
+        assert_eq!(all, json!({ "id": 1, "en_word": "apple", "ja_word": "りんご", "tags": ["food"] }));
+    }
+
+    #[test]
+    fn test_projection_keeps_only_selected_fields() {
+        let selection = FieldSelection::parse(Some("id,en_word"), ALLOWED).unwrap();
+        assert_eq!(selection.project(&word()).unwrap(), json!({ "id": 1, "en_word": "apple" }));
+
+        let selection = FieldSelection::parse(Some(" tags , id, tags"), ALLOWED).unwrap();
+        assert_eq!(selection.project(&word()).unwrap(), json!({ "tags": ["food"], "id": 1 }));
+
+        let projected = selection.project_all(&[word(), word()]).unwrap();
+        assert_eq!(projected.len(), 2);
+        assert_eq!(projected[1], json!({ "tags": ["food"], "id": 1 }));
+    }
+
+    #[derive(Serialize)]
+    struct Entry {
+        #[serde(serialize_with = "serialize_id")]
+        id: i32,
+        #[serde(serialize_with = "serialize_ids")]
+        related_ids: Vec<i32>,
+        count: i32,
+    }
+
+    #[test]
+    fn test_id_format_is_chosen_per_serialization() {
+        let entry = Entry { id: 1, related_ids: vec![2, 3], count: 4 };
+        assert_eq!(serde_json::to_value(&entry).unwrap(), json!({ "id": 1, "related_ids": [2, 3], "count": 4 }));
+        assert_eq!(
+            serde_json::to_value(WithIdFormat(&entry, IdFormat::Number)).unwrap(),
+            json!({ "id": 1, "related_ids": [2, 3], "count": 4 })
+        );
+
+        // Only the marked id fields change, however deep they are
+        let nested = serde_json::to_value(WithIdFormat(vec![&entry], IdFormat::String)).unwrap();
+        assert_eq!(nested, json!([{ "id": "1", "related_ids": ["2", "3"], "count": 4 }]));
+
+        // The format doesn't leak into the next serialization
+        assert_eq!(serde_json::to_value(&entry).unwrap()["id"], json!(1));
+        assert_eq!(IdFormat::from_flag(true), IdFormat::String);
+        assert_eq!(IdFormat::from_flag(false), IdFormat::Number);
+    }
+
+    #[test]
+    fn test_projection_keeps_string_ids() {
+        let entry = Entry { id: 1, related_ids: Vec::new(), count: 4 };
+        let selection = FieldSelection::parse(Some("id"), &["id", "count"]).unwrap();
+        let projected = selection.project(&WithIdFormat(&entry, IdFormat::String)).unwrap();
+        assert_eq!(projected, json!({ "id": "1" }));
+    }
+
+    #[test]
+    fn test_unknown_field_is_rejected() {
+        let err = FieldSelection::parse(Some("id,password"), ALLOWED).unwrap_err();
+        assert_eq!(err, "Unknown field 'password' in 'fields'. Must be one of: id, en_word, ja_word, tags");
+    }
+}
@@ -3,8 +3,17 @@
 pub mod user;
 pub mod post;
 pub mod vocabulary;
+pub mod pagination;
+pub mod fields;
+pub mod review;
+pub mod audit;
+pub mod normalize;
+pub mod search;
 
 // Re-export commonly used types
-pub use user::{User, CreateUserRequest, UpdateUserRequest};
-pub use post::{Post, CreatePostRequest};
-pub use vocabulary::{Vocabulary, CreateVocabularyRequest};
\ No newline at end of file
+pub use user::{User, UserSummary, CreateUserRequest, UpdateUserRequest, UpdateUserResponse, UserSearchParams};
+pub use post::{Post, CreatePostRequest, UpdatePostRequest};
+pub use vocabulary::{Vocabulary, CreateVocabularyRequest, UpdateVocabularyRequest, FavoriteRequest, DifficultyHeuristic, RandomVocabularyParams, VocabularyListParams};
+pub use normalize::Normalize;
+pub use pagination::PaginationParams;
+pub use review::{ReviewRequest, ReviewSchedule};
\ No newline at end of file
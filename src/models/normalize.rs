@@ -0,0 +1,74 @@
+/// リクエストボディの正規化 (前後の空白の除去、メールアドレスの小文字化など)。
+/// `ApiJson` がデシリアライズの直後に一度だけ呼ぶので、ハンドラには常に正規化済みの値が届く。
+/// 失敗しない変換だけを行い、入力の検証は引き続き各型の `validate()` が担当する。
+/// DB 層は正規化済みの値をそのまま保存するので、シードのように HTTP を通らない呼び出しは自分で `normalize()` を呼ぶこと。
+pub trait Normalize {
+    fn normalize(&mut self);
+}
+
+/// 型を持たない JSON はそのまま渡す。
+impl Normalize for serde_json::Value {
+    fn normalize(&mut self) {}
+}
+
+/// 前後の空白を取り除く。変化がなければ再確保しない。
+pub fn trim(value: &mut String) {
+    let trimmed = value.trim();
+    if trimmed.len() != value.len() {
+        *value = trimmed.to_string();
+    }
+}
+
+/// 前後の空白を取り除き、空になったら `None` にする。「空文字 = 未指定」として扱う任意項目用。
+pub fn trim_optional(value: &mut Option<String>) {
+    if let Some(inner) = value.as_mut() {
+        trim(inner);
+    }
+    if value.as_deref() == Some("") {
+        *value = None;
+    }
+}
+
+/// 連続する空白を 1 つにまとめ、前後の空白を取り除く。
+pub fn collapse_whitespace(value: &mut String) {
+    let collapsed = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed != *value {
+        *value = collapsed;
+    }
+}
+
+/// 前後の空白を取り除いて小文字にする。メールアドレスや言語コードのように大小を区別しない値用。
+pub fn trim_lowercase(value: &mut String) {
+    let normalized = value.trim().to_lowercase();
+    if normalized != *value {
+        *value = normalized;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_helpers() {
+        let mut name = "  Alice Smith ".to_string();
+        trim(&mut name);
+        assert_eq!(name, "Alice Smith");
+
+        let mut email = " Alice@Example.COM ".to_string();
+        trim_lowercase(&mut email);
+        assert_eq!(email, "alice@example.com");
+
+        let mut word = "  ice \t cream ".to_string();
+        collapse_whitespace(&mut word);
+        assert_eq!(word, "ice cream");
+
+        let mut content = Some("  hello ".to_string());
+        trim_optional(&mut content);
+        assert_eq!(content.as_deref(), Some("hello"));
+
+        let mut blank = Some("   ".to_string());
+        trim_optional(&mut blank);
+        assert_eq!(blank, None);
+    }
+}
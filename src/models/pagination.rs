@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::config::PaginationConfig;
+
+/// `PAGINATION_DEFAULT_LIMIT` 未設定時の、`limit` を省略した場合の件数。
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+
+/// `PAGINATION_MAX_LIMIT` 未設定時の、1 ページで返せる最大件数。これを超える `limit` は丸める。
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+/// 一覧系エンドポイント共通の `limit`/`offset` クエリパラメータ。
+/// ハンドラは直接使わず、`Pagination` 抽出器経由で `PaginationConfig` を適用した値を受け取る。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaginationParams {
+    /// 取得件数。省略時は `PAGINATION_DEFAULT_LIMIT` (20) 件、`PAGINATION_MAX_LIMIT` (100) を超えた値は丸められる
+    pub limit: Option<i64>,
+    /// 読み飛ばす件数
+    pub offset: Option<i64>,
+}
+
+impl PaginationParams {
+    /// 既定値と上限を適用する。`limit` が 1 未満・`offset` が負ならエラー。
+    pub fn resolve(&self, config: &PaginationConfig) -> Result<Pagination, String> {
+        let limit = match self.limit {
+            None => config.default_limit,
+            Some(limit) if limit < 1 => return Err("limit must be at least 1".to_string()),
+            Some(limit) => limit.min(config.max_limit),
+        };
+        let offset = match self.offset {
+            None => 0,
+            Some(offset) if offset < 0 => return Err("offset cannot be negative".to_string()),
+            Some(offset) => offset,
+        };
+
+        Ok(Pagination {
+            limit,
+            offset,
+            clamped: self.limit.is_some_and(|limit| limit > config.max_limit),
+            requested: self.limit.is_some() || self.offset.is_some(),
+        })
+    }
+}
+
+/// `PaginationConfig` を適用済みの `limit`/`offset`。一覧系ハンドラは抽出器としてこれを受け取る
+/// (`crate::extract` の `FromRequestParts` 実装を参照)。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pagination {
+    pub limit: i64,
+    pub offset: i64,
+    /// 要求された `limit` が上限に丸められた
+    pub clamped: bool,
+    /// `limit`/`offset` のどちらかが指定された。従来ページングしていなかった一覧で、
+    /// 指定がなければ全件を返し続ける (後方互換) ための判定に使う。
+    pub requested: bool,
+}
+
+/// `?envelope=true` を付けた一覧のレスポンス。配列の代わりに `{"data": [...], "page": {...}}` で返す。
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub page: PageInfo,
+}
+
+/// エンベロープに含めるページ情報。`total` は絞り込み条件に合う全ページ分の件数。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct PageInfo {
+    pub limit: i64,
+    pub offset: i64,
+    pub total: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(limit: Option<i64>, offset: Option<i64>) -> Result<Pagination, String> {
+        PaginationParams { limit, offset }.resolve(&PaginationConfig::default())
+    }
+
+    #[test]
+    fn test_pagination_defaults() {
+        let pagination = resolve(None, None).unwrap();
+        assert_eq!(pagination.limit, DEFAULT_PAGE_LIMIT);
+        assert_eq!(pagination.offset, 0);
+        assert!(!pagination.clamped);
+    }
+
+    #[test]
+    fn test_pagination_clamps_limit() {
+        let pagination = resolve(Some(MAX_PAGE_LIMIT + 1), Some(40)).unwrap();
+        assert_eq!(pagination.limit, MAX_PAGE_LIMIT);
+        assert_eq!(pagination.offset, 40);
+        assert!(pagination.clamped);
+
+        assert!(!resolve(Some(MAX_PAGE_LIMIT), None).unwrap().clamped);
+    }
+
+    #[test]
+    fn test_pagination_follows_configured_limits() {
+        let config = PaginationConfig { default_limit: 5, max_limit: 10 };
+
+        let default = PaginationParams::default().resolve(&config).unwrap();
+        assert_eq!(default.limit, 5);
+
+        let clamped = PaginationParams { limit: Some(11), offset: None }.resolve(&config).unwrap();
+        assert_eq!(clamped.limit, 10);
+        assert!(clamped.clamped);
+    }
+
+    #[test]
+    fn test_pagination_rejects_invalid_values() {
+        assert_eq!(resolve(Some(0), None), Err("limit must be at least 1".to_string()));
+        assert_eq!(resolve(Some(-5), None), Err("limit must be at least 1".to_string()));
+        assert_eq!(resolve(None, Some(-1)), Err("offset cannot be negative".to_string()));
+    }
+
+    #[test]
+    fn test_paginated_envelope_shape() {
+        let envelope = Paginated {
+            data: vec!["apple", "book"],
+            page: PageInfo { limit: 2, offset: 4, total: 9 },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&envelope).unwrap(),
+            serde_json::json!({ "data": ["apple", "book"], "page": { "limit": 2, "offset": 4, "total": 9 } })
+        );
+    }
+
+    #[test]
+    fn test_pagination_is_requested() {
+        assert!(!resolve(None, None).unwrap().requested);
+        assert!(resolve(Some(5), None).unwrap().requested);
+        assert!(resolve(None, Some(0)).unwrap().requested);
+    }
+}
@@ -1,22 +1,130 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
+use utoipa::ToSchema;
+
+use super::normalize::{self, Normalize};
+use super::user::UserSummary;
+use crate::config::PostLimits;
 
 /// ユーザーが作成した投稿を表すモデル。
 /// 本文は `Option<String>` として NULL も許可している。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Post {
     pub id: Uuid,
     pub user_id: Uuid,
     pub title: String,
     pub content: Option<String>,
+    /// 楽観ロック用のバージョン。更新に成功するたびに 1 ずつ増える。
+    #[serde(default = "initial_post_version")]
+    pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// 作成直後の投稿のバージョン (DB の列のデフォルト値)。
+pub const INITIAL_POST_VERSION: i32 = 1;
+
+fn initial_post_version() -> i32 {
+    INITIAL_POST_VERSION
+}
+
+/// `POST_TITLE_MAX_LEN` 未設定時のタイトルの最大長。
+pub const DEFAULT_POST_TITLE_MAX_LEN: usize = 200;
+
+/// `POST_CONTENT_MAX_LEN` 未設定時の本文の最大長。
+pub const DEFAULT_POST_CONTENT_MAX_LEN: usize = 10000;
+
+/// `posts.title` 列の長さ (`VARCHAR(500)`)。タイトルの上限はこれより大きくできない。
+pub const POST_TITLE_COLUMN_LEN: usize = 500;
+
+/// `?embed=author` 指定時の投稿。`Post` の項目はそのまま平らに並び、作者が `author` に入る。
+/// メールアドレスは含めないよう、作者は `UserSummary` で返す。
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PostWithAuthor {
+    #[serde(flatten)]
+    pub post: Post,
+    pub author: UserSummary,
+}
+
+/// `?truncate=<n>` 指定時の投稿 (`Post` または `PostWithAuthor`)。一覧やプレビュー向けに本文を短くしたもの。
+/// 元の項目はそのまま平らに並び、本文を切り詰めたかどうかが `content_truncated` に入る。
+#[derive(Debug, Clone, Serialize)]
+pub struct PostPreview<T> {
+    #[serde(flatten)]
+    pub post: T,
+    pub content_truncated: bool,
+}
+
+/// 切り詰めた本文の末尾に付ける省略記号。
+pub const TRUNCATION_ELLIPSIS: char = '…';
+
+/// 本文を持つ投稿のレスポンス型。`PostPreview` が本文を切り詰めるのに使う。
+pub trait PostContent {
+    fn content_mut(&mut self) -> &mut Option<String>;
+}
+
+impl PostContent for Post {
+    fn content_mut(&mut self) -> &mut Option<String> {
+        &mut self.content
+    }
+}
+
+impl PostContent for PostWithAuthor {
+    fn content_mut(&mut self) -> &mut Option<String> {
+        &mut self.post.content
+    }
+}
+
+impl<T: PostContent> PostPreview<T> {
+    /// 本文を `max_chars` 文字までに切り詰めたプレビューを作る。
+    pub fn new(mut post: T, max_chars: usize) -> Self {
+        let content_truncated = truncate_content(post.content_mut(), max_chars);
+        PostPreview { post, content_truncated }
+    }
+}
+
+/// 本文が `max_chars` 文字を超えていれば、先頭 `max_chars` 文字に `…` を付けたものに置き換えて `true` を返す。
+/// 文字数は `char` (Unicode スカラー値) で数え、`char` の境界で切るので、マルチバイト文字が途中で割れることはない。
+pub fn truncate_content(content: &mut Option<String>, max_chars: usize) -> bool {
+    let Some(text) = content.as_mut() else {
+        return false;
+    };
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => {
+            text.truncate(cut);
+            text.push(TRUNCATION_ELLIPSIS);
+            true
+        }
+        None => false,
+    }
+}
+
+/// `?truncate=` を検証する。未指定なら切り詰めない (`None`)。0 は本文が省略記号だけになるので 400 にする。
+pub fn validate_truncate(truncate: Option<usize>) -> Result<Option<usize>, String> {
+    match truncate {
+        Some(0) => Err("truncate must be at least 1".to_string()),
+        other => Ok(other),
+    }
+}
+
+/// `?embed=` で指定できる値。今は作者 (`author`) だけ。
+pub const EMBED_AUTHOR: &str = "author";
+
+/// `?embed=` を解釈し、作者を埋め込むかを返す。未指定・空なら `false`、知らない値はエラーメッセージを返す。
+pub fn parse_embed(embed: Option<&str>) -> Result<bool, String> {
+    match embed.map(str::trim) {
+        None | Some("") => Ok(false),
+        Some(EMBED_AUTHOR) => Ok(true),
+        Some(other) => Err(format!("Invalid embed '{}'. Must be '{}'", other, EMBED_AUTHOR)),
+    }
+}
+
 /// ポスト作成 API の入力。
 /// `Uuid` 型を直接使うことで、JSON 受信時に自動で形式チェックされる。
-#[derive(Debug, Deserialize)]
+/// 未知のフィールドは 400 にする。
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreatePostRequest {
     pub user_id: Uuid,
     pub title: String,
@@ -34,6 +142,7 @@ impl Post {
             user_id,
             title,
             content,
+            version: INITIAL_POST_VERSION,
             created_at: now,
             updated_at: now,
         }
@@ -55,9 +164,9 @@ impl Post {
 }
 
 impl CreatePostRequest {
-    /// タイトル必須・長さ制限、本文の最大長などを検証する。
+    /// タイトル必須・長さ制限、本文の最大長などを検証する。上限は `limits` (`Config::post_limits`) に従う。
     /// `Uuid` の妥当性は serde が先にチェック済みという前提でコメントが添えてある。
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self, limits: &PostLimits) -> Result<(), String> {
         // Note: user_id is already validated as UUID by serde deserialization
         
         // Validate title
@@ -65,50 +174,133 @@ impl CreatePostRequest {
             return Err("Title cannot be empty".to_string());
         }
         
-        if self.title.len() > 200 {
-            return Err("Title cannot exceed 200 characters".to_string());
+        if self.title.len() > limits.title_max_len {
+            return Err(format!("Title cannot exceed {} characters", limits.title_max_len));
         }
 
         // Validate content if provided
         if let Some(ref content) = self.content {
-            if content.len() > 10000 {
-                return Err("Content cannot exceed 10000 characters".to_string());
+            if content.len() > limits.content_max_len {
+                return Err(format!("Content cannot exceed {} characters", limits.content_max_len));
             }
         }
 
         Ok(())
     }
+}
+
+impl Normalize for CreatePostRequest {
+    /// タイトルをトリムし、空白だけの本文は `None` にする。DB 層はこの正規化済みの値をそのまま保存する。
+    fn normalize(&mut self) {
+        normalize::trim(&mut self.title);
+        normalize::trim_optional(&mut self.content);
+    }
+}
+
+/// ポスト更新 API の入力。
+/// `expected_version` はクライアントが最後に読んだ `version` で、他の更新と競合した場合は 409 になる。
+/// `title`/`content` は省略すると変更しない。`content` に空文字を渡すと本文を消す。
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePostRequest {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub expected_version: i32,
+}
+
+impl UpdatePostRequest {
+    /// 作成時と同じ長さ制限を適用し、`expected_version` が正の値であることを確認する。
+    pub fn validate(&self, limits: &PostLimits) -> Result<(), String> {
+        if self.expected_version < INITIAL_POST_VERSION {
+            return Err(format!("expected_version must be at least {}", INITIAL_POST_VERSION));
+        }
+
+        if let Some(ref title) = self.title {
+            if title.trim().is_empty() {
+                return Err("Title cannot be empty".to_string());
+            }
+
+            if title.len() > limits.title_max_len {
+                return Err(format!("Title cannot exceed {} characters", limits.title_max_len));
+            }
+        }
+
+        if let Some(ref content) = self.content {
+            if content.len() > limits.content_max_len {
+                return Err(format!("Content cannot exceed {} characters", limits.content_max_len));
+            }
+        }
 
-    /// 入力を正規化して `Post` に変換する。
-    /// 本文は空文字なら None に落とすことで、DB 上の null と同義にしている。
-    pub fn into_post(self) -> Post {
-        let normalized_content = self.content
-            .map(|c| c.trim().to_string())
-            .filter(|c| !c.is_empty());
-            
-        Post::new(
-            self.user_id,
-            self.title.trim().to_string(),
-            normalized_content,
-        )
+        Ok(())
     }
+}
+
+/// 指定したタイムゾーンにおける (月, 日) を返す。
+/// 「今日と同じ月日」の判定で、UTC の日付境界に引きずられないようにするためのヘルパー。
+pub fn month_day_in_offset(at: DateTime<Utc>, offset: FixedOffset) -> (i32, i32) {
+    let local = at.with_timezone(&offset);
+    (local.month() as i32, local.day() as i32)
+}
+
+/// 投稿一覧を作成日時で絞り込む範囲。どちらの端も省略でき、指定した時刻ちょうども含む。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CreatedAtRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
 
-    /// タイトルをトリムするだけの補助メソッド。
-    /// `self.title` を直接いじるのではなく、専用関数を用意することで再利用性が上がる。
-    pub fn get_normalized_title(&self) -> String {
-        self.title.trim().to_string()
+impl Normalize for UpdatePostRequest {
+    /// タイトルと本文をトリムする。本文の `Some("")` は「本文を消す」という意味なので `None` にはしない。
+    fn normalize(&mut self) {
+        if let Some(title) = self.title.as_mut() {
+            normalize::trim(title);
+        }
+        if let Some(content) = self.content.as_mut() {
+            normalize::trim(content);
+        }
     }
+}
+
+impl CreatedAtRange {
+    /// RFC3339 形式の `from`/`to` を解釈し、`from <= to` であることを確認する。
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> Result<Self, String> {
+        let range = CreatedAtRange {
+            from: parse_rfc3339_param("from", from)?,
+            to: parse_rfc3339_param("to", to)?,
+        };
+
+        if let (Some(from), Some(to)) = (range.from, range.to) {
+            if from > to {
+                return Err("'from' must not be later than 'to'".to_string());
+            }
+        }
 
-    /// 本文をトリムし、空なら None にする。
-    /// `Option::filter` を使うと「空文字を None にする」処理を 1 行で書ける。
-    pub fn get_normalized_content(&self) -> Option<String> {
-        self.content
-            .as_ref()
-            .map(|c| c.trim().to_string())
-            .filter(|c| !c.is_empty())
+        Ok(range)
     }
 }
 
+/// クエリパラメータの RFC3339 文字列を UTC の日時に変換する。未指定なら `None`。
+/// クエリ文字列の `+` はデコードで空白になるため、`2024-01-01T09:00:00 09:00` のようにオフセットの前が
+/// 空白になったものは `+09:00` として読む (`%2B` にエンコードしていないクライアント向け)。
+fn parse_rfc3339_param(name: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    value
+        .map(|value| {
+            let trimmed = value.trim();
+            DateTime::parse_from_rfc3339(trimmed)
+                .or_else(|err| match trimmed.rsplit_once(' ') {
+                    Some((at, offset)) if offset.len() == 5 => DateTime::parse_from_rfc3339(&format!("{}+{}", at, offset)),
+                    _ => Err(err),
+                })
+                .map(|at| at.with_timezone(&Utc))
+                .map_err(|_| {
+                    format!(
+                        "Invalid '{}' timestamp '{}'. Must be RFC3339, e.g. 2024-01-31T00:00:00Z",
+                        name, value
+                    )
+                })
+        })
+        .transpose()
+}
+
 /// UUID 文字列を `Uuid::parse_str` でチェックする小さなヘルパー。
 pub fn is_valid_uuid(uuid_str: &str) -> bool {
     Uuid::parse_str(uuid_str).is_ok()
@@ -118,6 +310,70 @@ pub fn is_valid_uuid(uuid_str: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_embed() {
+        assert_eq!(parse_embed(None), Ok(false));
+        assert_eq!(parse_embed(Some("")), Ok(false));
+        assert_eq!(parse_embed(Some("author")), Ok(true));
+        assert!(parse_embed(Some("comments")).is_err());
+    }
+
+    #[test]
+    fn test_post_with_author_flattens_post_fields() {
+        let post = Post::new(Uuid::new_v4(), "Title".to_string(), None);
+        let author = UserSummary { id: post.user_id, name: "Alice".to_string() };
+        let json = serde_json::to_value(PostWithAuthor { post: post.clone(), author }).unwrap();
+
+        assert_eq!(json["id"], post.id.to_string());
+        assert_eq!(json["title"], "Title");
+        assert_eq!(json["author"]["name"], "Alice");
+        assert!(json["author"].get("email").is_none());
+    }
+
+    #[test]
+    fn test_truncate_content_respects_char_boundaries() {
+        let mut content = Some("Hello, world".to_string());
+        assert!(truncate_content(&mut content, 5));
+        assert_eq!(content.as_deref(), Some("Hello…"));
+
+        // Multibyte characters are counted as one and never split
+        let mut content = Some("こんにちは世界".to_string());
+        assert!(truncate_content(&mut content, 5));
+        assert_eq!(content.as_deref(), Some("こんにちは…"));
+
+        let mut emoji = Some("🍎🍊🍋".to_string());
+        assert!(truncate_content(&mut emoji, 1));
+        assert_eq!(emoji.as_deref(), Some("🍎…"));
+
+        // Content at or under the limit is left alone
+        let mut exact = Some("こんにちは".to_string());
+        assert!(!truncate_content(&mut exact, 5));
+        assert_eq!(exact.as_deref(), Some("こんにちは"));
+
+        let mut none = None;
+        assert!(!truncate_content(&mut none, 5));
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn test_post_preview_flattens_and_flags_truncation() {
+        let post = Post::new(Uuid::new_v4(), "Title".to_string(), Some("日本語の本文です".to_string()));
+        let json = serde_json::to_value(PostPreview::new(post.clone(), 3)).unwrap();
+        assert_eq!(json["id"], post.id.to_string());
+        assert_eq!(json["content"], "日本語…");
+        assert_eq!(json["content_truncated"], true);
+
+        let author = UserSummary { id: post.user_id, name: "Alice".to_string() };
+        let json = serde_json::to_value(PostPreview::new(PostWithAuthor { post, author }, 100)).unwrap();
+        assert_eq!(json["content"], "日本語の本文です");
+        assert_eq!(json["content_truncated"], false);
+        assert_eq!(json["author"]["name"], "Alice");
+
+        assert!(validate_truncate(Some(0)).is_err());
+        assert_eq!(validate_truncate(Some(80)), Ok(Some(80)));
+        assert_eq!(validate_truncate(None), Ok(None));
+    }
+
     #[test]
     fn test_post_creation() {
         let user_id = Uuid::new_v4();
@@ -186,7 +442,7 @@ mod tests {
             title: "Test Title".to_string(),
             content: Some("Test content".to_string()),
         };
-        assert!(valid_request.validate().is_ok());
+        assert!(valid_request.validate(&PostLimits::default()).is_ok());
 
         // Valid request without content
         let valid_request_no_content = CreatePostRequest {
@@ -194,7 +450,7 @@ mod tests {
             title: "Test Title".to_string(),
             content: None,
         };
-        assert!(valid_request_no_content.validate().is_ok());
+        assert!(valid_request_no_content.validate(&PostLimits::default()).is_ok());
 
         // Empty title
         let invalid_title = CreatePostRequest {
@@ -202,7 +458,7 @@ mod tests {
             title: "".to_string(),
             content: None,
         };
-        assert!(invalid_title.validate().is_err());
+        assert!(invalid_title.validate(&PostLimits::default()).is_err());
 
         // Title too long
         let long_title = CreatePostRequest {
@@ -210,7 +466,7 @@ mod tests {
             title: "a".repeat(201),
             content: None,
         };
-        assert!(long_title.validate().is_err());
+        assert!(long_title.validate(&PostLimits::default()).is_err());
 
         // Content too long
         let long_content = CreatePostRequest {
@@ -218,38 +474,84 @@ mod tests {
             title: "Test Title".to_string(),
             content: Some("a".repeat(10001)),
         };
-        assert!(long_content.validate().is_err());
+        assert!(long_content.validate(&PostLimits::default()).is_err());
     }
 
     #[test]
-    fn test_create_post_request_into_post() {
-        let user_id = Uuid::new_v4();
+    fn test_post_validation_uses_configured_limits() {
+        let limits = PostLimits::new(10, 20).unwrap();
         let request = CreatePostRequest {
-            user_id,
-            title: "  Test Title  ".to_string(),
-            content: Some("  Test content  ".to_string()),
+            user_id: Uuid::new_v4(),
+            title: "a".repeat(11),
+            content: None,
         };
-        
-        let post = request.into_post();
-        
-        assert_eq!(post.user_id, user_id);
-        assert_eq!(post.title, "Test Title");
-        assert_eq!(post.content, Some("Test content".to_string()));
+        assert_eq!(request.validate(&limits).unwrap_err(), "Title cannot exceed 10 characters");
+        // The default limit would have accepted it
+        assert!(request.validate(&PostLimits::default()).is_ok());
+
+        let request = CreatePostRequest {
+            user_id: Uuid::new_v4(),
+            title: "Short".to_string(),
+            content: Some("a".repeat(21)),
+        };
+        assert_eq!(request.validate(&limits).unwrap_err(), "Content cannot exceed 20 characters");
+
+        let update: UpdatePostRequest =
+            serde_json::from_str(r#"{"content":"a longer body than twenty","expected_version":1}"#).unwrap();
+        assert_eq!(update.validate(&limits).unwrap_err(), "Content cannot exceed 20 characters");
+
+        // A raised limit lets longer posts through
+        let generous = PostLimits::new(300, 20000).unwrap();
+        let long = CreatePostRequest {
+            user_id: Uuid::new_v4(),
+            title: "a".repeat(250),
+            content: Some("a".repeat(15000)),
+        };
+        assert!(long.validate(&generous).is_ok());
     }
 
     #[test]
     fn test_create_post_request_normalization() {
         let user_id = Uuid::new_v4();
-        let request = CreatePostRequest {
+        let mut request = CreatePostRequest {
             user_id,
             title: "  Test Title  ".to_string(),
+            content: Some("  Test content  ".to_string()),
+        };
+        request.normalize();
+
+        assert_eq!(request.user_id, user_id);
+        assert_eq!(request.title, "Test Title");
+        assert_eq!(request.content, Some("Test content".to_string()));
+
+        let mut blank = CreatePostRequest {
+            user_id,
+            title: "Test Title".to_string(),
             content: Some("   ".to_string()), // Only whitespace
         };
-        
-        let post = request.into_post();
-        
-        assert_eq!(post.title, "Test Title");
-        assert_eq!(post.content, None); // Empty content should be None
+        blank.normalize();
+        assert_eq!(blank.content, None); // Empty content should be None
+    }
+
+    #[test]
+    fn test_post_from_one_year_ago_matches_today() {
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let today = DateTime::parse_from_rfc3339("2025-06-15T12:00:00Z").unwrap().with_timezone(&Utc);
+        let mut post = Post::new(Uuid::new_v4(), "Memory".to_string(), None);
+        post.created_at = today.with_year(today.year() - 1).unwrap();
+
+        assert_eq!(month_day_in_offset(post.created_at, utc), month_day_in_offset(today, utc));
+        assert_ne!(month_day_in_offset(today - chrono::Duration::days(1), utc), month_day_in_offset(today, utc));
+    }
+
+    #[test]
+    fn test_month_day_respects_timezone_offset() {
+        let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let at = DateTime::parse_from_rfc3339("2025-06-14T20:00:00Z").unwrap().with_timezone(&Utc);
+
+        assert_eq!(month_day_in_offset(at, utc), (6, 14));
+        assert_eq!(month_day_in_offset(at, jst), (6, 15));
     }
 
     #[test]
@@ -269,13 +571,14 @@ mod tests {
             user_id: Uuid::parse_str("987fcdeb-51a2-43d1-9f12-345678901234").unwrap(),
             title: "Test Post".to_string(),
             content: Some("This is test content".to_string()),
+            version: 3,
             created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
         };
 
         // Test serialization to JSON
         let json = serde_json::to_string(&post).expect("Failed to serialize post");
-        let expected = r#"{"id":"123e4567-e89b-12d3-a456-426614174000","user_id":"987fcdeb-51a2-43d1-9f12-345678901234","title":"Test Post","content":"This is test content","created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
+        let expected = r#"{"id":"123e4567-e89b-12d3-a456-426614174000","user_id":"987fcdeb-51a2-43d1-9f12-345678901234","title":"Test Post","content":"This is test content","version":3,"created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
         assert_eq!(json, expected);
     }
 
@@ -286,13 +589,14 @@ mod tests {
             user_id: Uuid::parse_str("987fcdeb-51a2-43d1-9f12-345678901234").unwrap(),
             title: "Test Post".to_string(),
             content: None,
+            version: INITIAL_POST_VERSION,
             created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
         };
 
         // Test serialization to JSON with null content
         let json = serde_json::to_string(&post).expect("Failed to serialize post");
-        let expected = r#"{"id":"123e4567-e89b-12d3-a456-426614174000","user_id":"987fcdeb-51a2-43d1-9f12-345678901234","title":"Test Post","content":null,"created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
+        let expected = r#"{"id":"123e4567-e89b-12d3-a456-426614174000","user_id":"987fcdeb-51a2-43d1-9f12-345678901234","title":"Test Post","content":null,"version":1,"created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
         assert_eq!(json, expected);
     }
 
@@ -307,6 +611,7 @@ mod tests {
         assert_eq!(post.user_id, Uuid::parse_str("987fcdeb-51a2-43d1-9f12-345678901234").unwrap());
         assert_eq!(post.title, "Test Post");
         assert_eq!(post.content, Some("This is test content".to_string()));
+        assert_eq!(post.version, INITIAL_POST_VERSION);
         assert_eq!(post.created_at, DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc));
         assert_eq!(post.updated_at, DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc));
     }
@@ -352,4 +657,67 @@ mod tests {
         assert_eq!(request.title, "Test Post");
         assert_eq!(request.content, None);
     }
+
+    #[test]
+    fn test_created_at_range_parsing() {
+        assert_eq!(CreatedAtRange::parse(None, None).unwrap(), CreatedAtRange::default());
+
+        let range = CreatedAtRange::parse(Some("2024-01-01T09:00:00+09:00"), None).unwrap();
+        assert_eq!(range.from.unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(range.to, None);
+
+        let same_instant = CreatedAtRange::parse(Some("2024-01-01T00:00:00Z"), Some("2024-01-01T00:00:00Z"));
+        assert!(same_instant.is_ok());
+    }
+
+    #[test]
+    fn test_created_at_range_accepts_an_unencoded_plus_offset() {
+        // `?from=2024-01-01T09:00:00+09:00` arrives with the `+` decoded to a space
+        let uri: axum::http::Uri = "/api/posts?from=2024-01-01T09:00:00+09:00".parse().unwrap();
+        let axum::extract::Query(query) =
+            axum::extract::Query::<std::collections::HashMap<String, String>>::try_from_uri(&uri).unwrap();
+        assert_eq!(query["from"], "2024-01-01T09:00:00 09:00");
+
+        let range = CreatedAtRange::parse(Some(&query["from"]), None).unwrap();
+        assert_eq!(range.from.unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+
+        let encoded = CreatedAtRange::parse(Some("2024-01-01T09:00:00+09:00"), None).unwrap();
+        assert_eq!(range, encoded);
+
+        // A space anywhere else is still rejected
+        assert!(CreatedAtRange::parse(Some("2024-01-01 09:00"), None).is_err());
+    }
+
+    #[test]
+    fn test_created_at_range_rejects_invalid_input() {
+        let inverted = CreatedAtRange::parse(Some("2024-02-01T00:00:00Z"), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(inverted.unwrap_err(), "'from' must not be later than 'to'");
+
+        let not_rfc3339 = CreatedAtRange::parse(None, Some("2024-01-01"));
+        assert!(not_rfc3339.unwrap_err().contains("Invalid 'to' timestamp"));
+    }
+
+    #[test]
+    fn test_update_post_request_validation() {
+        let mut request: UpdatePostRequest =
+            serde_json::from_str(r#"{"title":"  New title ","content":"  ","expected_version":2}"#).unwrap();
+        assert!(request.validate(&PostLimits::default()).is_ok());
+        request.normalize();
+        assert_eq!(request.title, Some("New title".to_string()));
+        assert_eq!(request.content, Some(String::new())); // Still "clear the content", not "leave it"
+
+        let mut untouched: UpdatePostRequest = serde_json::from_str(r#"{"expected_version":1}"#).unwrap();
+        untouched.normalize();
+        assert_eq!(untouched.title, None);
+        assert_eq!(untouched.content, None);
+
+        let invalid_version: UpdatePostRequest = serde_json::from_str(r#"{"expected_version":0}"#).unwrap();
+        assert!(invalid_version.validate(&PostLimits::default()).is_err());
+
+        let empty_title: UpdatePostRequest =
+            serde_json::from_str(r#"{"title":"   ","expected_version":1}"#).unwrap();
+        assert_eq!(empty_title.validate(&PostLimits::default()).unwrap_err(), "Title cannot be empty");
+
+        assert!(serde_json::from_str::<UpdatePostRequest>(r#"{"title":"No version"}"#).is_err());
+    }
 }
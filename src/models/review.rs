@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use utoipa::ToSchema;
+
+use super::normalize::Normalize;
+
+/// 自己評価の最高点 (完璧に思い出せた)。
+pub const MAX_REVIEW_GRADE: i32 = 5;
+
+/// これ未満の評価は「思い出せなかった」として最初からやり直す。
+pub const PASSING_REVIEW_GRADE: i32 = 3;
+
+/// 未復習の語彙の易しさ係数 (SM-2 の初期値)。
+pub const INITIAL_EASE_FACTOR: f32 = 2.5;
+
+/// 易しさ係数の下限。これより下げると復習間隔が伸びなくなる。
+pub const MIN_EASE_FACTOR: f32 = 1.3;
+
+/// 復習間隔の上限 (日、約 100 年)。正解が続くと間隔は指数的に伸び、`DateTime` の範囲を超えてしまうため。
+pub const MAX_REVIEW_INTERVAL_DAYS: i64 = 36500;
+
+fn initial_ease_factor() -> f32 {
+    INITIAL_EASE_FACTOR
+}
+
+/// 語彙ごとの間隔反復 (SM-2) の復習状態。`Vocabulary` に平坦化して埋め込む。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReviewSchedule {
+    /// 連続して正解した回数 (SM-2 の n)。評価が `PASSING_REVIEW_GRADE` 未満なら 0 に戻る
+    #[serde(default)]
+    pub review_count: i32,
+    /// 易しさ係数。初期値 2.5、下限 1.3
+    #[serde(default = "initial_ease_factor")]
+    pub ease_factor: f32,
+    /// 次に復習すべき日時。未復習なら `null`
+    #[serde(default)]
+    pub next_review_at: Option<DateTime<Utc>>,
+    /// 最後に復習した日時。未復習なら `null`
+    #[serde(default)]
+    pub last_reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl Default for ReviewSchedule {
+    fn default() -> Self {
+        ReviewSchedule {
+            review_count: 0,
+            ease_factor: INITIAL_EASE_FACTOR,
+            next_review_at: None,
+            last_reviewed_at: None,
+        }
+    }
+}
+
+impl ReviewSchedule {
+    /// 前回決めた復習間隔 (日)。間隔は列として持たず、最終復習日時と次回復習日時の差から求める。
+    fn previous_interval_days(&self) -> i64 {
+        match (self.last_reviewed_at, self.next_review_at) {
+            (Some(last), Some(next)) => ((next - last).num_hours() as f64 / 24.0).round().max(1.0) as i64,
+            _ => 1,
+        }
+    }
+
+    /// `now` に評価 `grade` (0〜5) で復習した後の状態を SM-2 で求める。
+    /// 正解 (3 以上) なら 1 日 → 6 日 → 前回の間隔 × 易しさ係数と間隔を伸ばし、係数を評価に応じて増減させる。
+    /// 不正解なら係数は変えずに連続正解回数を 0 に戻し、翌日にもう一度出す。
+    /// 間隔は `MAX_REVIEW_INTERVAL_DAYS` で頭打ちにする。
+    pub fn after_review(&self, grade: i32, now: DateTime<Utc>) -> ReviewSchedule {
+        let (review_count, ease_factor, interval_days) = if grade < PASSING_REVIEW_GRADE {
+            (0, self.ease_factor, 1)
+        } else {
+            let interval_days = match self.review_count {
+                0 => 1,
+                1 => 6,
+                // Clamp before the cast; `as i64` would silently saturate on huge products
+                _ => (self.previous_interval_days() as f64 * self.ease_factor as f64)
+                    .round()
+                    .min(MAX_REVIEW_INTERVAL_DAYS as f64) as i64,
+            };
+            let gap = (MAX_REVIEW_GRADE - grade) as f32;
+            let ease_factor = (self.ease_factor + 0.1 - gap * (0.08 + gap * 0.02)).max(MIN_EASE_FACTOR);
+            (self.review_count + 1, ease_factor, interval_days)
+        };
+
+        ReviewSchedule {
+            review_count,
+            ease_factor,
+            next_review_at: Some(
+                now.checked_add_signed(Duration::days(interval_days))
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            ),
+            last_reviewed_at: Some(now),
+        }
+    }
+}
+
+/// `POST /api/vocabulary/:id/review` の入力。
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReviewRequest {
+    /// 思い出せた度合いの自己評価。0 (全く思い出せない) 〜 5 (完璧)
+    pub grade: i32,
+}
+
+/// 数値だけなので正規化するものはない。
+impl Normalize for ReviewRequest {
+    fn normalize(&mut self) {}
+}
+
+impl ReviewRequest {
+    /// 評価が 0〜5 の範囲か確認する。
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0..=MAX_REVIEW_GRADE).contains(&self.grade) {
+            return Err(format!("grade must be between 0 and {}", MAX_REVIEW_GRADE));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    fn assert_ease(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "ease factor {} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_successful_reviews_grow_the_interval() {
+        let first = ReviewSchedule::default().after_review(5, at("2024-01-01T00:00:00Z"));
+        assert_eq!(first.review_count, 1);
+        assert_ease(first.ease_factor, 2.6);
+        assert_eq!(first.next_review_at, Some(at("2024-01-02T00:00:00Z")));
+        assert_eq!(first.last_reviewed_at, Some(at("2024-01-01T00:00:00Z")));
+
+        let second = first.after_review(4, at("2024-01-02T00:00:00Z"));
+        assert_eq!(second.review_count, 2);
+        assert_ease(second.ease_factor, 2.6);
+        assert_eq!(second.next_review_at, Some(at("2024-01-08T00:00:00Z")));
+
+        // 6 days * 2.6 rounds to 16 days; a hard recall lowers the ease factor
+        let third = second.after_review(3, at("2024-01-08T00:00:00Z"));
+        assert_eq!(third.review_count, 3);
+        assert_ease(third.ease_factor, 2.46);
+        assert_eq!(third.next_review_at, Some(at("2024-01-24T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_failed_review_starts_over_without_changing_ease() {
+        let schedule = ReviewSchedule {
+            review_count: 4,
+            ease_factor: 2.2,
+            next_review_at: Some(at("2024-02-01T00:00:00Z")),
+            last_reviewed_at: Some(at("2024-01-10T00:00:00Z")),
+        };
+
+        let failed = schedule.after_review(1, at("2024-02-01T00:00:00Z"));
+        assert_eq!(failed.review_count, 0);
+        assert_ease(failed.ease_factor, 2.2);
+        assert_eq!(failed.next_review_at, Some(at("2024-02-02T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_ease_factor_has_a_floor() {
+        let schedule = ReviewSchedule { review_count: 1, ease_factor: 1.35, ..ReviewSchedule::default() };
+        assert_ease(schedule.after_review(3, Utc::now()).ease_factor, MIN_EASE_FACTOR);
+    }
+
+    #[test]
+    fn test_interval_is_capped_after_many_perfect_reviews() {
+        let mut now = at("2024-01-01T00:00:00Z");
+        let mut schedule = ReviewSchedule::default();
+        for _ in 0..40 {
+            // Review when due, as a learner who never misses would
+            schedule = schedule.after_review(MAX_REVIEW_GRADE, now);
+            now = schedule.next_review_at.unwrap();
+        }
+
+        let last = schedule.last_reviewed_at.unwrap();
+        assert_eq!(schedule.review_count, 40);
+        assert_eq!((now - last).num_days(), MAX_REVIEW_INTERVAL_DAYS);
+    }
+
+    #[test]
+    fn test_review_request_grade_range() {
+        assert!(ReviewRequest { grade: 0 }.validate().is_ok());
+        assert!(ReviewRequest { grade: 5 }.validate().is_ok());
+        assert!(ReviewRequest { grade: -1 }.validate().is_err());
+        assert!(ReviewRequest { grade: 6 }.validate().is_err());
+    }
+}
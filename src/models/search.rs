@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::{post::Post, user::User, vocabulary::Vocabulary};
+
+/// 検索語の最大文字数。
+pub const MAX_SEARCH_QUERY_CHARS: usize = 100;
+
+/// `limit` を省略した場合の、カテゴリごとの件数。
+pub const DEFAULT_SEARCH_LIMIT: i64 = 5;
+
+/// カテゴリごとに返せる最大件数。これを超える `limit` は丸める。
+pub const MAX_SEARCH_LIMIT: i64 = 50;
+
+/// `ILIKE` に渡すパターン (`%q%`) を作る。空白だけの検索語はエラー。
+/// メールアドレスは小文字で保存しているので、検索語も同じように小文字化する。
+/// `%`・`_`・`\` はワイルドカードにならないようエスケープする。
+pub fn like_pattern(q: Option<&str>) -> Result<String, String> {
+    let q = q.map(str::trim).unwrap_or_default();
+    if q.is_empty() {
+        return Err("q must not be blank".to_string());
+    }
+    if q.chars().count() > MAX_SEARCH_QUERY_CHARS {
+        return Err(format!("q cannot exceed {} characters", MAX_SEARCH_QUERY_CHARS));
+    }
+
+    let mut pattern = String::with_capacity(q.len() + 2);
+    pattern.push('%');
+    for c in q.to_lowercase().chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    Ok(pattern)
+}
+
+/// 横断検索で探す対象。`all` なら全カテゴリ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchType {
+    All,
+    Users,
+    Posts,
+    Vocabulary,
+}
+
+impl SearchType {
+    /// `?type=` の値を読む。省略時は `All`、大文字小文字は区別しない。
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        let value = value.map(str::trim).unwrap_or_default();
+        if value.is_empty() || value.eq_ignore_ascii_case("all") {
+            Ok(Self::All)
+        } else if value.eq_ignore_ascii_case("users") {
+            Ok(Self::Users)
+        } else if value.eq_ignore_ascii_case("posts") {
+            Ok(Self::Posts)
+        } else if value.eq_ignore_ascii_case("vocabulary") {
+            Ok(Self::Vocabulary)
+        } else {
+            Err("type must be one of all, users, posts, vocabulary".to_string())
+        }
+    }
+
+    /// このカテゴリを検索するかどうか。
+    pub fn includes(self, category: SearchType) -> bool {
+        self == Self::All || self == category
+    }
+}
+
+/// `GET /api/search` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    /// 部分一致させる検索語 (大文字小文字を区別しない)。ユーザーは名前・メールアドレス、投稿はタイトル、
+    /// 語彙は英単語・訳語・例文が対象
+    pub q: Option<String>,
+    /// 検索するカテゴリ (`all`・`users`・`posts`・`vocabulary`)。省略時は `all`
+    #[serde(rename = "type")]
+    pub search_type: Option<String>,
+    /// カテゴリごとの最大件数。省略時は 5 件、50 を超えた値は丸められる
+    pub limit: Option<i64>,
+}
+
+impl SearchParams {
+    /// `ILIKE` に渡すパターン。`like_pattern` を参照。
+    pub fn like_pattern(&self) -> Result<String, String> {
+        like_pattern(self.q.as_deref())
+    }
+
+    pub fn search_type(&self) -> Result<SearchType, String> {
+        SearchType::parse(self.search_type.as_deref())
+    }
+
+    /// カテゴリごとの件数。1 未満はエラー、上限を超えた値は丸める。
+    pub fn limit(&self) -> Result<i64, String> {
+        match self.limit {
+            None => Ok(DEFAULT_SEARCH_LIMIT),
+            Some(limit) if limit < 1 => Err("limit must be at least 1".to_string()),
+            Some(limit) => Ok(limit.min(MAX_SEARCH_LIMIT)),
+        }
+    }
+}
+
+/// `GET /api/search` のレスポンス。`type` で絞り込んだ場合、検索しなかったカテゴリのキーは含めない。
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct SearchResults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<User>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub posts: Option<Vec<Post>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vocabulary: Option<Vec<Vocabulary>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(q: &str, search_type: Option<&str>, limit: Option<i64>) -> SearchParams {
+        SearchParams {
+            q: Some(q.to_string()),
+            search_type: search_type.map(str::to_string),
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_search_type_selects_categories() {
+        let all = params("apple", None, None).search_type().unwrap();
+        assert_eq!(all, SearchType::All);
+        for category in [SearchType::Users, SearchType::Posts, SearchType::Vocabulary] {
+            assert!(all.includes(category));
+        }
+
+        let posts = params("apple", Some("Posts"), None).search_type().unwrap();
+        assert!(posts.includes(SearchType::Posts));
+        assert!(!posts.includes(SearchType::Users));
+        assert!(!posts.includes(SearchType::Vocabulary));
+
+        assert!(params("apple", Some("comments"), None).search_type().is_err());
+    }
+
+    #[test]
+    fn test_search_limit_defaults_and_clamps() {
+        assert_eq!(params("apple", None, None).limit(), Ok(DEFAULT_SEARCH_LIMIT));
+        assert_eq!(params("apple", None, Some(3)).limit(), Ok(3));
+        assert_eq!(params("apple", None, Some(1000)).limit(), Ok(MAX_SEARCH_LIMIT));
+        assert!(params("apple", None, Some(0)).limit().is_err());
+    }
+
+    #[test]
+    fn test_search_query_is_escaped_and_required() {
+        assert_eq!(params(" 100%_Apple ", None, None).like_pattern(), Ok("%100\\%\\_apple%".to_string()));
+        assert!(params("   ", None, None).like_pattern().is_err());
+        assert!(SearchParams::default().like_pattern().is_err());
+    }
+
+    #[test]
+    fn test_search_results_omit_categories_that_were_not_searched() {
+        let results = SearchResults { posts: Some(Vec::new()), ..Default::default() };
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json, serde_json::json!({ "posts": [] }));
+    }
+}
@@ -1,21 +1,62 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use email_address::{EmailAddress, Options};
+use utoipa::{IntoParams, ToSchema};
+
+use super::normalize::{self, Normalize};
+use super::post::{CreatePostRequest, Post};
+use super::search::{like_pattern, MAX_SEARCH_QUERY_CHARS};
+use crate::config::PostLimits;
 
 /// 登録済みユーザーを表すドメインモデル。
 /// `serde::{Serialize, Deserialize}` を derive しているので、そのまま JSON へシリアライズ可能。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct User {
     pub id: Uuid,
     pub name: String,
     pub email: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 論理削除された日時。有効なユーザーでは `None` で、JSON にも出力しない。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// メールアドレスの確認が済んでいるか。作成時とメールアドレスの変更時は `false` になる
+    #[serde(default)]
+    pub email_verified: bool,
+}
+
+/// 有効な (論理削除されていない) ユーザーの `email` を一意にする部分インデックスの名前。
+pub const EMAIL_UNIQUE_INDEX: &str = "idx_users_email_active";
+
+/// 論理削除を入れる前の、テーブル全体で `email` を一意にしていた制約。マイグレーションで `EMAIL_UNIQUE_INDEX` に置き換える。
+pub const LEGACY_EMAIL_UNIQUE_CONSTRAINT: &str = "users_email_key";
+
+/// `PUT /api/users/:id` のレスポンス。更新後のユーザーに、再確認が必要かどうかを添える。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UpdateUserResponse {
+    #[serde(flatten)]
+    pub user: User,
+    /// メールアドレスが変わり、`POST /api/users/:id/verify-email` で確認し直す必要がある
+    pub email_verification_required: bool,
+}
+
+/// 他リソースから参照するときの簡易表現 (ID と名前のみ)。
+/// メールアドレスなどの個人情報は含めない。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub name: String,
 }
 
+/// トリム後に更新する値が何も残らなかったときの 400 メッセージ。
+pub const NO_VALID_UPDATE_FIELDS: &str = "No valid fields to update";
+
 /// ユーザー作成 API が受け取るペイロード。
-/// `Deserialize` のみ実装し、DB 保存時には `CreateUserRequest::into_user` で `User` に変換する。
-#[derive(Debug, Deserialize)]
+/// `Deserialize` のみ実装し、`Normalize` で正規化した値を DB 層が `User` に変換して保存する。
+/// 未知のフィールドは無視せず 400 にする (クライアントの綴り間違いなどに気付けるように)。
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreateUserRequest {
     pub name: String,
     pub email: String,
@@ -23,8 +64,11 @@ pub struct CreateUserRequest {
 
 /// ユーザー更新 API の入力。
 /// 更新しないフィールドは `None` を渡すため、`Option<String>` として定義している。
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
+    /// 省略可。指定する場合はパスのユーザー ID と一致していること
+    #[serde(default)]
+    pub id: Option<Uuid>,
     pub name: Option<String>,
     pub email: Option<String>,
 }
@@ -41,6 +85,8 @@ impl User {
             email,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
+            email_verified: false,
         }
     }
 
@@ -52,11 +98,30 @@ impl User {
         }
         
         if let Some(new_email) = email {
+            if self.email_change_requires_verification(Some(&new_email)) {
+                self.email_verified = false;
+            }
             self.email = new_email;
         }
         
         self.updated_at = Utc::now();
     }
+
+    /// `new_email` (正規化済み) への更新でメールアドレスが変わり、確認をやり直す必要があるか。
+    pub fn email_change_requires_verification(&self, new_email: Option<&str>) -> bool {
+        new_email.is_some_and(|new_email| new_email != self.email)
+    }
+
+    /// メールアドレスを確認済みにする。
+    pub fn verify_email(&mut self) {
+        self.email_verified = true;
+        self.updated_at = Utc::now();
+    }
+
+    /// 論理削除済みかどうか。
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
 
 impl CreateUserRequest {
@@ -87,11 +152,27 @@ impl CreateUserRequest {
 
         Ok(())
     }
+}
+
+impl Normalize for CreateUserRequest {
+    /// 名前をトリムし、メールアドレスはトリムして小文字にする。重複チェックも保存もこの値で行うので、
+    /// 大文字小文字違いの登録も衝突として扱える。
+    fn normalize(&mut self) {
+        normalize::trim(&mut self.name);
+        normalize::trim_lowercase(&mut self.email);
+    }
+}
 
-    /// 受け取った入力をトリム・小文字化して `User` に変換する。
-    /// フィールドをクリーンアップする責務をこの層に閉じ込めることで、DB 層の複雑さを減らしている。
-    pub fn into_user(self) -> User {
-        User::new(self.name.trim().to_string(), self.email.trim().to_lowercase())
+impl Normalize for UpdateUserRequest {
+    /// 送られたフィールドだけを `CreateUserRequest` と同じ規則で正規化する。
+    /// 空白だけの値は `Some("")` のまま残し、`validate()` で 400 にする。
+    fn normalize(&mut self) {
+        if let Some(name) = self.name.as_mut() {
+            normalize::trim(name);
+        }
+        if let Some(email) = self.email.as_mut() {
+            normalize::trim_lowercase(email);
+        }
     }
 }
 
@@ -104,6 +185,12 @@ impl UpdateUserRequest {
             return Err("At least one field (name or email) must be provided for update".to_string());
         }
 
+        // A whitespace-only name on its own would otherwise turn into an update of updated_at alone
+        let is_blank = |value: &Option<String>| value.as_deref().unwrap_or("").trim().is_empty();
+        if is_blank(&self.name) && is_blank(&self.email) {
+            return Err(NO_VALID_UPDATE_FIELDS.to_string());
+        }
+
         // Validate name if provided
         if let Some(ref name) = self.name {
             if name.trim().is_empty() {
@@ -133,51 +220,127 @@ impl UpdateUserRequest {
         Ok(())
     }
 
-    /// 名前をトリムし、空なら `None` にするユーティリティ。
-    /// 返り値も `Option<String>` なので、そのまま SQL の動的組み立てに流用できる。
-    pub fn get_normalized_name(&self) -> Option<String> {
-        self.name.as_ref().map(|n| n.trim().to_string())
+    /// 本文に `id` がある場合、パスのユーザー ID と一致するか確認する。
+    pub fn check_path_id(&self, path_id: Uuid) -> Result<(), String> {
+        match self.id {
+            Some(id) if id != path_id => Err("User ID in body does not match the path".to_string()),
+            _ => Ok(()),
+        }
     }
+}
+
+/// `POST /api/users/with-posts` で一緒に作れる投稿の最大件数。
+pub const MAX_INITIAL_POSTS: usize = 20;
+
+/// `POST /api/users/with-posts` で一緒に作る投稿。作者は作成するユーザーになるので `user_id` は受け付けない。
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct InitialPostRequest {
+    pub title: String,
+    pub content: Option<String>,
+}
 
-    /// メールアドレスをトリムして小文字化する。
-    /// メールは大小区別しないことが多いため、ここで正規化しておくと照合漏れを防げる。
-    pub fn get_normalized_email(&self) -> Option<String> {
-        self.email.as_ref().map(|e| e.trim().to_lowercase())
+impl InitialPostRequest {
+    /// 作成するユーザーを作者にした `CreatePostRequest` に変換する。検証や正規化はそちらに任せる。
+    pub fn for_user(self, user_id: Uuid) -> CreatePostRequest {
+        CreatePostRequest {
+            user_id,
+            title: self.title,
+            content: self.content,
+        }
     }
 }
 
-/// シンプルなメールフォーマット検証。
-/// 正規表現を使わず、`split('@')` などで最小限のルールをチェックしている。
-fn is_valid_email(email: &str) -> bool {
-    // Basic email validation - contains @ and has parts before and after
-    let parts: Vec<&str> = email.split('@').collect();
-    
-    if parts.len() != 2 {
-        return false;
+impl Normalize for InitialPostRequest {
+    /// `CreatePostRequest` と同じく、タイトルをトリムし、空白だけの本文は `None` にする。
+    fn normalize(&mut self) {
+        normalize::trim(&mut self.title);
+        normalize::trim_optional(&mut self.content);
     }
-    
-    let local = parts[0];
-    let domain = parts[1];
-    
-    // Check local part
-    if local.is_empty() || local.len() > 64 {
-        return false;
+}
+
+/// `POST /api/users/with-posts` の入力。オンボーディングでユーザーと最初の投稿を 1 往復で作るためのもの。
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateUserWithPostsRequest {
+    pub user: CreateUserRequest,
+    /// 省略時は投稿なし
+    #[serde(default)]
+    pub posts: Vec<InitialPostRequest>,
+}
+
+impl Normalize for CreateUserWithPostsRequest {
+    fn normalize(&mut self) {
+        self.user.normalize();
+        self.posts.iter_mut().for_each(Normalize::normalize);
     }
-    
-    // Check domain part
-    if domain.is_empty() || domain.len() > 253 {
-        return false;
+}
+
+impl CreateUserWithPostsRequest {
+    /// DB に触れる前にユーザーと全投稿を検証する。投稿のエラーには `posts[<index>]` を付けて、どれが悪いか分かるようにする。
+    pub fn validate(&self, limits: &PostLimits) -> Result<(), String> {
+        self.user.validate()?;
+
+        if self.posts.len() > MAX_INITIAL_POSTS {
+            return Err(format!("posts cannot contain more than {} entries", MAX_INITIAL_POSTS));
+        }
+        for (index, post) in self.posts.iter().enumerate() {
+            // Only title/content are checked, so the placeholder author doesn't matter
+            let request = CreatePostRequest {
+                user_id: Uuid::nil(),
+                title: post.title.clone(),
+                content: post.content.clone(),
+            };
+            request.validate(limits).map_err(|e| format!("posts[{}]: {}", index, e))?;
+        }
+
+        Ok(())
     }
-    
-    // Domain should contain at least one dot
-    if !domain.contains('.') {
+}
+
+/// `POST /api/users/with-posts` のレスポンス。投稿はリクエストと同じ順に並ぶ。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserWithPostsResponse {
+    pub user: User,
+    pub posts: Vec<Post>,
+}
+
+/// 検索語の最大文字数。
+pub const MAX_USER_SEARCH_QUERY_CHARS: usize = MAX_SEARCH_QUERY_CHARS;
+
+/// `GET /api/users/search` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSearchParams {
+    /// 名前またはメールアドレスに部分一致させる検索語 (大文字小文字を区別しない)
+    pub q: Option<String>,
+}
+
+impl UserSearchParams {
+    /// `ILIKE` に渡すパターン (`%q%`) を作る。空白だけの検索語はエラー。
+    /// 組み立て方は `GET /api/search` と共通 (`search::like_pattern` を参照)。
+    pub fn like_pattern(&self) -> Result<String, String> {
+        like_pattern(self.q.as_deref())
+    }
+}
+
+/// メールアドレス全体の最大長 (RFC 5321 のパス長制限から導かれる 254 文字)。
+const MAX_EMAIL_LEN: usize = 254;
+
+/// `email_address` クレートによる RFC 5322 準拠のメールフォーマット検証。
+/// 引用符付きローカル部や IDN ドメインを受け付ける一方、表示名付き (`Name <a@b.com>`)・
+/// ドメインリテラル (`a@[127.0.0.1]`)・ドットのないドメインは実運用に合わせて拒否する。
+fn is_valid_email(email: &str) -> bool {
+    if email.len() > MAX_EMAIL_LEN {
         return false;
     }
-    
-    // Basic character validation
-    let valid_chars = |c: char| c.is_alphanumeric() || ".-_+".contains(c);
-    
-    local.chars().all(valid_chars) && domain.chars().all(|c| c.is_alphanumeric() || ".-".contains(c))
+
+    let options = Options::default()
+        .with_required_tld()
+        .without_domain_literal()
+        .without_display_text();
+
+    EmailAddress::parse_with_options(email, options).is_ok()
 }
 
 #[cfg(test)]
@@ -236,10 +399,30 @@ mod tests {
         assert!(invalid_email.validate().is_err());
     }
 
+    #[test]
+    fn test_duplicate_email_conflicts_after_normalization() {
+        let mut first = CreateUserRequest {
+            name: "John Doe".to_string(),
+            email: "john@example.com".to_string(),
+        };
+        let mut second = CreateUserRequest {
+            name: " Johnny ".to_string(),
+            email: "  John@Example.COM ".to_string(),
+        };
+        first.normalize();
+        second.normalize();
+
+        // Both creates resolve to the same key used by Database::email_exists
+        assert_eq!(first.email, second.email);
+        assert_eq!(second.email, "john@example.com");
+        assert_eq!(second.name, "Johnny");
+    }
+
     #[test]
     fn test_update_user_request_validation() {
         // Valid update with name
         let valid_update = UpdateUserRequest {
+            id: None,
             name: Some("Jane Doe".to_string()),
             email: None,
         };
@@ -247,6 +430,7 @@ mod tests {
 
         // Empty update
         let empty_update = UpdateUserRequest {
+            id: None,
             name: None,
             email: None,
         };
@@ -254,12 +438,48 @@ mod tests {
 
         // Invalid email in update
         let invalid_email_update = UpdateUserRequest {
+            id: None,
             name: None,
             email: Some("invalid-email".to_string()),
         };
         assert!(invalid_email_update.validate().is_err());
     }
 
+    #[test]
+    fn test_whitespace_only_name_update_has_no_valid_fields() {
+        let update = UpdateUserRequest {
+            id: None,
+            name: Some("   ".to_string()),
+            email: None,
+        };
+        assert_eq!(update.validate(), Err(NO_VALID_UPDATE_FIELDS.to_string()));
+
+        // With a real email alongside, the blank name is reported on its own
+        let with_email = UpdateUserRequest {
+            id: None,
+            name: Some("   ".to_string()),
+            email: Some("jane@example.com".to_string()),
+        };
+        assert_eq!(with_email.validate(), Err("Name cannot be empty".to_string()));
+    }
+
+    #[test]
+    fn test_update_user_request_body_id_must_match_path() {
+        let path_id = Uuid::new_v4();
+        let mut update = UpdateUserRequest {
+            id: None,
+            name: Some("Jane Doe".to_string()),
+            email: None,
+        };
+        assert!(update.check_path_id(path_id).is_ok());
+
+        update.id = Some(path_id);
+        assert!(update.check_path_id(path_id).is_ok());
+
+        update.id = Some(Uuid::new_v4());
+        assert!(update.check_path_id(path_id).is_err());
+    }
+
     #[test]
     fn test_email_validation() {
         assert!(is_valid_email("test@example.com"));
@@ -273,6 +493,33 @@ mod tests {
         assert!(!is_valid_email(""));
     }
 
+    #[test]
+    fn test_email_validation_tricky_valid_cases() {
+        assert!(is_valid_email("first.middle.last@example.com"));
+        assert!(is_valid_email("\"john doe\"@example.com"));
+        assert!(is_valid_email("\"very.(),:;<>[]\\\".unusual\"@example.com"));
+        assert!(is_valid_email("user@bücher.example"));
+        assert!(is_valid_email("user@sub.domain.example.co.jp"));
+        assert!(is_valid_email("o'brien@example.ie"));
+        assert!(is_valid_email(&format!("{}@example.com", "a".repeat(64))));
+    }
+
+    #[test]
+    fn test_email_validation_tricky_invalid_cases() {
+        assert!(!is_valid_email(".leading@example.com"));
+        assert!(!is_valid_email("trailing.@example.com"));
+        assert!(!is_valid_email("double..dot@example.com"));
+        assert!(!is_valid_email("two@@example.com"));
+        assert!(!is_valid_email("a@b@example.com"));
+        assert!(!is_valid_email("john doe@example.com"));
+        assert!(!is_valid_email("user@-example.com"));
+        assert!(!is_valid_email("user@example..com"));
+        assert!(!is_valid_email("John <john@example.com>"));
+        assert!(!is_valid_email("user@[127.0.0.1]"));
+        assert!(!is_valid_email(&format!("{}@example.com", "a".repeat(65))));
+        assert!(!is_valid_email(&format!("user@{}.com", "a".repeat(250))));
+    }
+
     #[test]
     fn test_user_serialization() {
         let user = User {
@@ -281,12 +528,60 @@ mod tests {
             email: "john@example.com".to_string(),
             created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
+            deleted_at: None,
+            email_verified: true,
         };
 
         // Test serialization to JSON
         let json = serde_json::to_string(&user).expect("Failed to serialize user");
-        let expected = r#"{"id":"123e4567-e89b-12d3-a456-426614174000","name":"John Doe","email":"john@example.com","created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
+        let expected = r#"{"id":"123e4567-e89b-12d3-a456-426614174000","name":"John Doe","email":"john@example.com","created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z","email_verified":true}"#;
         assert_eq!(json, expected);
+
+        // The update response is the user plus the re-verification flag
+        let response = UpdateUserResponse { user, email_verification_required: false };
+        let json: serde_json::Value = serde_json::to_value(&response).expect("Failed to serialize response");
+        assert_eq!(json["email"], "john@example.com");
+        assert_eq!(json["email_verification_required"], false);
+    }
+
+    #[test]
+    fn test_changing_email_resets_verification() {
+        let mut user = User::new("John Doe".to_string(), "john@example.com".to_string());
+        assert!(!user.email_verified);
+
+        user.verify_email();
+        assert!(user.email_verified);
+
+        // Renaming or re-sending the same address keeps the verification
+        assert!(!user.email_change_requires_verification(None));
+        assert!(!user.email_change_requires_verification(Some("john@example.com")));
+        user.update(Some("Johnny".to_string()), Some("john@example.com".to_string()));
+        assert!(user.email_verified);
+
+        assert!(user.email_change_requires_verification(Some("jane@example.com")));
+        user.update(None, Some("jane@example.com".to_string()));
+        assert!(!user.email_verified);
+
+        user.verify_email();
+        assert!(user.email_verified);
+    }
+
+    #[test]
+    fn test_soft_deleted_user_serialization() {
+        let mut user = User::new("John Doe".to_string(), "john@example.com".to_string());
+        assert!(!user.is_deleted());
+
+        user.deleted_at = Some(DateTime::parse_from_rfc3339("2022-01-02T00:00:00Z").unwrap().with_timezone(&Utc));
+        assert!(user.is_deleted());
+
+        // Deleted users (only visible with include_deleted=true) expose deleted_at
+        let json: serde_json::Value = serde_json::to_value(&user).expect("Failed to serialize user");
+        assert_eq!(json["deleted_at"], "2022-01-02T00:00:00Z");
+
+        // A new user with the same email is independent of the soft-deleted one
+        let recreated = User::new("John Doe".to_string(), "john@example.com".to_string());
+        assert_ne!(recreated.id, user.id);
+        assert!(!recreated.is_deleted());
     }
 
     #[test]
@@ -301,6 +596,7 @@ mod tests {
         assert_eq!(user.email, "john@example.com");
         assert_eq!(user.created_at, DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc));
         assert_eq!(user.updated_at, DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc));
+        assert_eq!(user.deleted_at, None);
     }
 
     #[test]
@@ -333,4 +629,60 @@ mod tests {
         assert_eq!(request.name, None);
         assert_eq!(request.email, Some("updated@example.com".to_string()));
     }
+
+    #[test]
+    fn test_user_search_pattern() {
+        let params = UserSearchParams { q: Some("  John@ ".to_string()) };
+        assert_eq!(params.like_pattern(), Ok("%john@%".to_string()));
+
+        // LIKE wildcards in the query are matched literally
+        let wildcard = UserSearchParams { q: Some("100%_a\\b".to_string()) };
+        assert_eq!(wildcard.like_pattern(), Ok("%100\\%\\_a\\\\b%".to_string()));
+    }
+
+    #[test]
+    fn test_user_search_rejects_blank_query() {
+        for q in [None, Some(""), Some("   ")] {
+            let params = UserSearchParams { q: q.map(str::to_string) };
+            assert_eq!(params.like_pattern(), Err("q must not be blank".to_string()));
+        }
+
+        let too_long = UserSearchParams { q: Some("a".repeat(MAX_USER_SEARCH_QUERY_CHARS + 1)) };
+        assert!(too_long.like_pattern().is_err());
+    }
+
+    #[test]
+    fn test_create_user_with_posts_validates_everything_up_front() {
+        let request: CreateUserWithPostsRequest = serde_json::from_str(
+            r#"{"user": {"name": "Jane", "email": "jane@example.com"}, "posts": [{"title": "Hello"}, {"title": " ", "content": "x"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(request.validate(&PostLimits::default()), Err("posts[1]: Title cannot be empty".to_string()));
+
+        let no_posts: CreateUserWithPostsRequest =
+            serde_json::from_str(r#"{"user": {"name": "Jane", "email": "not-an-email"}}"#).unwrap();
+        assert!(no_posts.posts.is_empty());
+        assert_eq!(no_posts.validate(&PostLimits::default()), Err("Invalid email format".to_string()));
+
+        let too_many = CreateUserWithPostsRequest {
+            user: CreateUserRequest { name: "Jane".to_string(), email: "jane@example.com".to_string() },
+            posts: (0..=MAX_INITIAL_POSTS)
+                .map(|i| InitialPostRequest { title: format!("Post {}", i), content: None })
+                .collect(),
+        };
+        assert!(too_many.validate(&PostLimits::default()).unwrap_err().starts_with("posts cannot contain more than"));
+    }
+
+    #[test]
+    fn test_initial_post_cannot_name_another_author() {
+        let err = serde_json::from_str::<InitialPostRequest>(
+            r#"{"title": "Hello", "user_id": "00000000-0000-0000-0000-000000000000"}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown field `user_id`"));
+
+        let user_id = Uuid::new_v4();
+        let request = InitialPostRequest { title: "Hello".to_string(), content: None }.for_user(user_id);
+        assert_eq!(request.user_id, user_id);
+    }
 }
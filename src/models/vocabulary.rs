@@ -1,98 +1,1059 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
 
-/// 英単語と和訳、および例文を保持する語彙モデル。
+use super::{
+    fields::{serialize_id, serialize_ids, FieldSelection, IdFormat},
+    normalize::{self, Normalize},
+    review::ReviewSchedule,
+};
+
+/// 英単語と訳語、および例文を保持する語彙モデル。
 /// `SERIAL` 主キーを使うため、`id` は `i32` 型になっている。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 訳語の言語は `lang` で表し、`ja_word`/`ja_example` は互換性のため名前を変えずにその言語の訳語・例文を持つ。
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Vocabulary {
+    /// `?id_as_string=true` では JSON の文字列になる (`WithIdFormat`)
+    #[serde(serialize_with = "serialize_id")]
     pub id: i32,
     pub en_word: String,
     pub ja_word: String,
     pub en_example: Option<String>,
     pub ja_example: Option<String>,
+    /// 訳語の言語 (ISO 639-1)。
+    #[serde(default = "default_lang")]
+    pub lang: String,
+    /// 分類用のタグ (小文字)。
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 難易度。`DEFAULT_DIFFICULTY` (0) は未設定を表す。
+    #[serde(default)]
+    pub difficulty: i32,
+    /// 学習者がスターを付けた語彙か。`POST /api/vocabulary/:id/favorite` で切り替える
+    #[serde(default)]
+    pub favorite: bool,
+    /// 間隔反復の復習状態。JSON では `review_count` などがトップレベルに並ぶ。
+    #[serde(flatten, default)]
+    pub review: ReviewSchedule,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// `?fields=` で指定できる `Vocabulary` の JSON フィールド名。
+pub const VOCABULARY_FIELDS: &[&str] = &[
+    "id",
+    "en_word",
+    "ja_word",
+    "en_example",
+    "ja_example",
+    "lang",
+    "tags",
+    "difficulty",
+    "favorite",
+    "review_count",
+    "ease_factor",
+    "next_review_at",
+    "last_reviewed_at",
+    "created_at",
+    "updated_at",
+];
+
+/// `GET /api/vocabulary/:id` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetVocabularyQuery {
+    /// 返すフィールドのカンマ区切り (例: `id,en_word,ja_word`)。省略時は全フィールド
+    pub fields: Option<String>,
+    /// `true` なら `id` を JSON の文字列で返す
+    #[serde(default)]
+    pub id_as_string: bool,
+}
+
+impl GetVocabularyQuery {
+    /// `fields` を解釈する。未知のフィールド名はエラー。
+    pub fn field_selection(&self) -> Result<FieldSelection, String> {
+        FieldSelection::parse(self.fields.as_deref(), VOCABULARY_FIELDS)
+    }
+
+    /// `id_as_string` に対応する `id` の型。
+    pub fn id_format(&self) -> IdFormat {
+        IdFormat::from_flag(self.id_as_string)
+    }
+}
+
+/// `GET /api/vocabulary/:id/examples` のレスポンス。単語を先に見せて例文は後から読み込む UI 向けに、
+/// 例文だけを返す。例文がない場合は `null`。
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct VocabularyExamples {
+    pub en_example: Option<String>,
+    pub ja_example: Option<String>,
+}
+
+impl From<Vocabulary> for VocabularyExamples {
+    fn from(vocabulary: Vocabulary) -> Self {
+        VocabularyExamples {
+            en_example: vocabulary.en_example,
+            ja_example: vocabulary.ja_example,
+        }
+    }
+}
+
+/// 語彙 1 件あたりに残す変更履歴の件数。これより古いスナップショットは、更新のたびに同じトランザクションで削除する。
+pub const VOCABULARY_HISTORY_LIMIT: i64 = 50;
+
+/// `GET /api/vocabulary/:id/history` の 1 件。`PATCH` (または upsert) で上書きされる直前の値と、上書きされた日時。
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct VocabularyHistoryEntry {
+    pub id: i64,
+    #[serde(serialize_with = "serialize_id")]
+    pub vocabulary_id: i32,
+    pub en_word: String,
+    pub ja_word: String,
+    pub en_example: Option<String>,
+    pub ja_example: Option<String>,
+    pub tags: Vec<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// 語彙作成 (または `?upsert=true` による更新) の結果。
+/// `Vocabulary` のフィールドに加えて、新規作成なら `inserted: true`、既存行の更新なら `false` を返す。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateVocabularyResponse {
+    #[serde(flatten)]
+    pub vocabulary: Vocabulary,
+    pub inserted: bool,
+}
+
+/// `POST /api/vocabulary` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateVocabularyQuery {
+    /// `true` なら既存の英単語を 409 にせず更新する
+    #[serde(default)]
+    pub upsert: bool,
+}
+
+/// `en_word` を言語ごとに大文字小文字を区別せず一意にする関数インデックス (`LOWER(en_word), lang`) の名前。
+/// 保存する値は入力どおりの大文字小文字のまま。
+pub const EN_WORD_UNIQUE_INDEX: &str = "idx_vocabulary_en_word_lang_unique";
+
+/// 言語を区別しなかった頃の一意インデックス。マイグレーションで `EN_WORD_UNIQUE_INDEX` に置き換える。
+pub const LEGACY_EN_WORD_UNIQUE_INDEX: &str = "idx_vocabulary_en_word_lower_unique";
+
+/// 1 つの語彙に付けられるタグの最大数。
+pub const MAX_TAGS: usize = 10;
+
+/// タグ 1 つあたりの最大文字数。
+pub const MAX_TAG_CHARS: usize = 30;
+
+/// 英単語・和訳の最大バイト数。
+pub const MAX_WORD_LEN: usize = 200;
+
+/// 例文の最大バイト数。
+pub const MAX_EXAMPLE_LEN: usize = 1000;
+
+/// 訳語の言語の既定値。`lang` 列ができる前の行もこの言語として扱う。
+pub const DEFAULT_LANG: &str = "ja";
+
+/// 訳語として受け付ける言語 (ISO 639-1)。
+pub const SUPPORTED_LANGS: &[&str] = &["ja", "zh", "ko", "es", "fr", "de", "it", "pt", "ru", "vi", "th", "id"];
+
+fn default_lang() -> String {
+    DEFAULT_LANG.to_string()
+}
+
+/// 言語コードをトリム・小文字化し、`SUPPORTED_LANGS` に含まれるか確認する。
+pub fn parse_lang(lang: &str) -> Result<String, String> {
+    let lang = lang.trim().to_lowercase();
+    if SUPPORTED_LANGS.contains(&lang.as_str()) {
+        Ok(lang)
+    } else {
+        Err(format!("Invalid lang '{}'. Must be one of: {}", lang, SUPPORTED_LANGS.join(", ")))
+    }
+}
+
+/// 同じ英単語 (大文字小文字違いを含む) が既にあるときの 409 メッセージ。
+pub const DUPLICATE_WORD_MESSAGE: &str = "Word already exists";
+
+/// 難易度が未設定であることを表す値 (DB の列のデフォルト値)。
+pub const DEFAULT_DIFFICULTY: i32 = 0;
+
+/// 英単語の長さから難易度を決めるしきい値。
+/// `[4, 6, 8, 10]` なら 4 文字以下が 1、6 文字以下が 2 … 10 文字を超えると 5 になる。
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifficultyHeuristic {
+    thresholds: Vec<usize>,
+}
+
 /// 語彙登録エンドポイントの入力。
 /// 例文は任意なので `Option<String>` として宣言している。
-#[derive(Debug, Deserialize)]
+/// 未知のフィールドは 400 にする (シードファイルの各要素も同じ)。
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct CreateVocabularyRequest {
     pub en_word: String,
     pub ja_word: String,
     pub en_example: Option<String>,
     pub ja_example: Option<String>,
+    /// 訳語の言語 (ISO 639-1)。省略時は `ja`
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// 分類用のタグ (最大 10 個、各 30 文字まで)。`?upsert=true` で省略した場合は既存のタグを保つ
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
 }
 
-impl CreateVocabularyRequest {
-    /// 単語・和訳の必須チェックと長さ制限を行う。
-    /// 例文は任意だが、上限 1000 文字を超えた場合はエラーにする。
+/// `PATCH /api/vocabulary/:id` の入力。送ったフィールドだけを更新する。
+/// 例文は「省略」と「`null`」を区別するため `Option<Option<String>>` で受け取る (`Post::update` の本文と同じ)。
+///
+/// | JSON                        | 値                 | 意味               |
+/// |-----------------------------|--------------------|--------------------|
+/// | キーなし                    | `None`             | 変更しない         |
+/// | `"en_example": null`        | `Some(None)`       | 例文を消す         |
+/// | `"en_example": "An apple."` | `Some(Some(..))`   | 例文を置き換える   |
+///
+/// 空白だけの例文は作成時と同じく「なし」として扱うので、`""` も例文を消す。
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateVocabularyRequest {
+    #[serde(default)]
+    pub en_word: Option<String>,
+    #[serde(default)]
+    pub ja_word: Option<String>,
+    /// 省略で変更なし、`null` で削除
+    #[serde(default, deserialize_with = "deserialize_present")]
+    #[schema(value_type = Option<String>)]
+    pub en_example: Option<Option<String>>,
+    /// 省略で変更なし、`null` で削除
+    #[serde(default, deserialize_with = "deserialize_present")]
+    #[schema(value_type = Option<String>)]
+    pub ja_example: Option<Option<String>>,
+    /// 指定するとタグを丸ごと置き換える (`[]` で全削除)
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+/// キーが存在すれば (値が `null` でも) `Some` にする。キーがなければ `#[serde(default)]` で `None` のまま。
+fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// `GET /api/vocabulary` の並び替え・絞り込み条件。
+/// 列名は必ず許可リスト経由で SQL に変換し、クエリ文字列をそのまま埋め込まないようにしている。
+/// `limit`/`offset` は `Pagination` 抽出器が読み、どちらも `envelope` もなければ従来どおり全件を返す。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VocabularyListParams {
+    /// 並び替える列 (`created_at`・`en_word`・`ja_word`)
+    pub sort: Option<String>,
+    /// `asc` または `desc`
+    pub order: Option<String>,
+    /// 英単語の前方一致 (大文字小文字は区別しない)
+    pub starts_with: Option<String>,
+    /// 訳語の言語 (ISO 639-1) で絞り込む
+    pub lang: Option<String>,
+    /// このタグが付いた語彙だけに絞り込む (大文字小文字は区別しない)
+    pub tag: Option<String>,
+    /// `true` ならスター付きの語彙だけ、`false` ならスターなしの語彙だけに絞り込む
+    pub favorite: Option<bool>,
+    /// `true` なら配列の代わりに `{"data": [...], "page": {"limit", "offset", "total"}}` で返す
+    #[serde(default)]
+    pub envelope: bool,
+    /// 返すフィールドのカンマ区切り (例: `id,en_word,ja_word`)。省略時は全フィールド。`page` には影響しない
+    pub fields: Option<String>,
+    /// `true` なら各要素の `id` を JSON の文字列で返す
+    #[serde(default)]
+    pub id_as_string: bool,
+}
+
+/// `POST /api/vocabulary/coverage` で受け付ける本文の最大文字数。
+pub const MAX_COVERAGE_TEXT_CHARS: usize = 20000;
+
+/// 語彙カバー率計算の入力。
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CoverageRequest {
+    pub text: String,
+}
+
+/// 語彙に登録済みの単語と、その語彙 ID。
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct KnownWord {
+    pub word: String,
+    pub id: i32,
+}
+
+/// 語彙カバー率計算の結果。
+/// `coverage_ratio` は本文中の異なり語のうち、語彙に登録済みのものの割合 (0.0〜1.0)。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CoverageResponse {
+    pub known: Vec<KnownWord>,
+    pub unknown: Vec<String>,
+    pub coverage_ratio: f64,
+}
+
+/// 本文は単語に分割するときに空白を無視するので、そのまま受け取る。
+impl Normalize for CoverageRequest {
+    fn normalize(&mut self) {}
+}
+
+impl CoverageRequest {
+    /// 本文が空でないこと、長すぎないことを確認する。
     pub fn validate(&self) -> Result<(), String> {
-        // Validate en_word (required)
-        if self.en_word.trim().is_empty() {
-            return Err("English word cannot be empty".to_string());
+        if self.text.trim().is_empty() {
+            return Err("Text cannot be empty".to_string());
         }
-        
-        if self.en_word.len() > 200 {
-            return Err("English word cannot exceed 200 characters".to_string());
+
+        if self.text.chars().count() > MAX_COVERAGE_TEXT_CHARS {
+            return Err(format!("Text cannot exceed {} characters", MAX_COVERAGE_TEXT_CHARS));
         }
 
-        // Validate ja_word (required)
-        if self.ja_word.trim().is_empty() {
-            return Err("Japanese word cannot be empty".to_string());
+        Ok(())
+    }
+
+    /// 本文を小文字の単語に分割し、出現順を保ったまま重複を取り除く。
+    /// 英字・数字・アポストロフィ・ハイフン以外を区切りとみなし、前後の記号は落とす。
+    pub fn tokenize(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.text
+            .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-'))
+            .map(|token| token.trim_matches(|c: char| c == '\'' || c == '-').to_lowercase())
+            .filter(|token| !token.is_empty())
+            .filter(|token| seen.insert(token.clone()))
+            .collect()
+    }
+}
+
+impl CoverageResponse {
+    /// トークン列と「小文字の単語 → 語彙 ID」の対応表からレスポンスを組み立てる。
+    pub fn from_matches(words: Vec<String>, matches: &HashMap<String, i32>) -> Self {
+        let total = words.len();
+        let mut known = Vec::new();
+        let mut unknown = Vec::new();
+
+        for word in words {
+            match matches.get(&word) {
+                Some(&id) => known.push(KnownWord { word, id }),
+                None => unknown.push(word),
+            }
         }
-        
-        if self.ja_word.len() > 200 {
-            return Err("Japanese word cannot exceed 200 characters".to_string());
+
+        let coverage_ratio = if total == 0 {
+            0.0
+        } else {
+            known.len() as f64 / total as f64
+        };
+
+        CoverageResponse { known, unknown, coverage_ratio }
+    }
+}
+
+impl DifficultyHeuristic {
+    /// しきい値は 1 以上かつ狭義の昇順でなければならない。
+    pub fn new(thresholds: Vec<usize>) -> Result<Self, String> {
+        if thresholds.is_empty() {
+            return Err("at least one threshold is required".to_string());
         }
 
-        // Validate en_example if provided (optional)
-        if let Some(ref example) = self.en_example {
-            if example.len() > 1000 {
-                return Err("English example cannot exceed 1000 characters".to_string());
+        if thresholds[0] == 0 || thresholds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("thresholds must be positive and strictly ascending".to_string());
+        }
+
+        Ok(DifficultyHeuristic { thresholds })
+    }
+
+    /// 割り当てられる最大の難易度 (しきい値の数 + 1)。
+    pub fn max_level(&self) -> i32 {
+        self.thresholds.len() as i32 + 1
+    }
+
+    /// 単語の文字数から難易度を求める。SQL 側の `case_expression` と同じ規則。
+    pub fn level_for(&self, word: &str) -> i32 {
+        let length = word.chars().count();
+        self.thresholds
+            .iter()
+            .position(|&threshold| length <= threshold)
+            .map_or(self.max_level(), |index| index as i32 + 1)
+    }
+
+    /// `level_for` と同じ規則の `CASE` 式を組み立てる。
+    /// 埋め込むのは検証済みの整数と呼び出し側が指定する固定の列名だけ。
+    pub fn case_expression(&self, column: &str) -> String {
+        let branches: String = self
+            .thresholds
+            .iter()
+            .enumerate()
+            .map(|(index, threshold)| format!(" WHEN char_length({}) <= {} THEN {}", column, threshold, index + 1))
+            .collect();
+        format!("CASE{} ELSE {} END", branches, self.max_level())
+    }
+}
+
+impl FromStr for DifficultyHeuristic {
+    type Err = String;
+
+    /// `"4,6,8,10"` のようなカンマ区切りのしきい値を読み取る。
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let thresholds = value
+            .split(',')
+            .map(|part| {
+                part.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("'{}' is not a valid length", part.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        DifficultyHeuristic::new(thresholds)
+    }
+}
+
+/// パスで受け取った語彙 ID を検証する。`SERIAL` は 1 から始まるため 0 以下は存在し得ない。
+pub fn validate_vocabulary_id(id: i32) -> Result<i32, String> {
+    if id < 1 {
+        return Err(format!("Vocabulary id must be a positive integer, got {}", id));
+    }
+    Ok(id)
+}
+
+/// `GET /api/vocabulary/batch` で一度に指定できる ID の最大数。
+pub const MAX_BATCH_IDS: usize = 200;
+
+/// `GET /api/vocabulary/batch` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VocabularyBatchParams {
+    /// カンマ区切りの語彙 ID (例: `1,2,3`)。最大 200 件
+    pub ids: Option<String>,
+}
+
+impl VocabularyBatchParams {
+    /// `ids` を語彙 ID の列に変換する。整数でない・0 以下の値はエラー。
+    /// 重複は最初の出現だけを残し、指定された順序は保つ。
+    pub fn parse_ids(&self) -> Result<Vec<i32>, String> {
+        let ids = parse_id_list("ids", self.ids.as_deref().unwrap_or_default(), MAX_BATCH_IDS)?;
+        if ids.is_empty() {
+            return Err("ids must contain at least one id".to_string());
+        }
+        Ok(ids)
+    }
+}
+
+/// カンマ区切りの語彙 ID を重複なしの列に変換する。空の要素は読み飛ばす。
+/// `name` はエラーメッセージに出すクエリパラメータ名。`max` 件を超えたらエラー。
+fn parse_id_list(name: &str, raw: &str, max: usize) -> Result<Vec<i32>, String> {
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for token in raw.split(',').map(str::trim) {
+        if token.is_empty() {
+            continue;
+        }
+        let id = token
+            .parse::<i32>()
+            .map_err(|_| format!("{} must be comma-separated integers, got '{}'", name, token))
+            .and_then(validate_vocabulary_id)?;
+        if seen.insert(id) {
+            ids.push(id);
+        }
+    }
+
+    if ids.len() > max {
+        return Err(format!("{} cannot contain more than {} ids", name, max));
+    }
+    Ok(ids)
+}
+
+/// `GET /api/vocabulary/batch` のレスポンス。
+/// 見つかった語彙を指定順に並べ、存在しなかった ID は `missing_ids` で返す (404 にはしない)。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VocabularyBatchResponse {
+    pub vocabulary: Vec<Vocabulary>,
+    #[serde(serialize_with = "serialize_ids")]
+    pub missing_ids: Vec<i32>,
+}
+
+impl VocabularyBatchResponse {
+    /// DB から取得した順不同の行を、要求された `ids` の順に並べ替える。
+    pub fn new(ids: &[i32], found: Vec<Vocabulary>) -> Self {
+        let mut by_id: HashMap<i32, Vocabulary> = found.into_iter().map(|v| (v.id, v)).collect();
+        let mut vocabulary = Vec::with_capacity(by_id.len());
+        let mut missing_ids = Vec::new();
+        for id in ids {
+            match by_id.remove(id) {
+                Some(entry) => vocabulary.push(entry),
+                None => missing_ids.push(*id),
             }
         }
+        VocabularyBatchResponse { vocabulary, missing_ids }
+    }
+}
 
-        // Validate ja_example if provided (optional)
+/// `GET /api/vocabulary/changes` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VocabularyChangesParams {
+    /// この日時 (RFC3339) 以降に作成・更新された語彙だけを返す。省略時は全件 (初回の完全同期)
+    pub since: Option<String>,
+}
+
+impl VocabularyChangesParams {
+    /// `since` を解釈する。省略・空なら UNIX エポック (全件が対象) を返す。
+    pub fn since(&self) -> Result<DateTime<Utc>, String> {
+        match self.since.as_deref().map(str::trim) {
+            None | Some("") => Ok(DateTime::<Utc>::UNIX_EPOCH),
+            Some(since) => DateTime::parse_from_rfc3339(since)
+                .map(|at| at.with_timezone(&Utc))
+                .map_err(|_| {
+                    format!("Invalid 'since' timestamp '{}'. Must be RFC3339, e.g. 2024-01-31T00:00:00Z", since)
+                }),
+        }
+    }
+}
+
+/// `GET /api/vocabulary/changes` のレスポンス。
+/// クライアントは `server_time` を保存し、次回の `since` にそのまま渡せば取りこぼしなく差分を受け取れる。
+/// 境界付近の語彙は次回も返ることがあるので、`id` で重複を除いて適用する。
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VocabularyChanges {
+    /// 次回の `since` に使うカーソル。読み出し時点でコミット前だった書き込みも拾えるよう、現在時刻より少し前になる
+    pub server_time: DateTime<Utc>,
+    /// `since` 以降に作成・更新された語彙 (`updated_at` の古い順)
+    pub changed: Vec<Vocabulary>,
+    /// 削除された語彙の ID。語彙を個別に削除する手段がまだないため、今は常に空
+    #[serde(serialize_with = "serialize_ids")]
+    pub deleted_ids: Vec<i32>,
+}
+
+/// `VOCABULARY_SEED_FILE` が未設定のときに投入する組み込みのシードデータ。
+pub fn default_seed_entries() -> Vec<CreateVocabularyRequest> {
+    [
+        ("apple", "りんご", "I eat an apple every day.", "私は毎日りんごを食べます。"),
+        ("book", "本", "This is an interesting book.", "これは面白い本です。"),
+        ("computer", "コンピューター", "I use my computer for work.", "私は仕事でコンピューターを使います。"),
+        ("study", "勉強する", "I study English every morning.", "私は毎朝英語を勉強します。"),
+        ("friend", "友達", "She is my best friend.", "彼女は私の親友です。"),
+    ]
+    .into_iter()
+    .map(|(en_word, ja_word, en_example, ja_example)| CreateVocabularyRequest {
+        en_word: en_word.to_string(),
+        ja_word: ja_word.to_string(),
+        en_example: Some(en_example.to_string()),
+        ja_example: Some(ja_example.to_string()),
+        lang: None,
+        tags: None,
+    })
+    .collect()
+}
+
+/// シードファイル (`CreateVocabularyRequest` の JSON 配列) を読み込み、全件を検証する。
+/// 1 件でも不正なら、何番目の要素かを含めたエラーを返して何も投入しない。
+pub fn parse_seed_entries(json: &str) -> Result<Vec<CreateVocabularyRequest>, String> {
+    let entries: Vec<CreateVocabularyRequest> = serde_json::from_str(json)
+        .map_err(|e| format!("Seed file must be a JSON array of vocabulary entries: {}", e))?;
+
+    for (index, entry) in entries.iter().enumerate() {
+        entry
+            .validate()
+            .map_err(|e| format!("Seed entry #{} ('{}') is invalid: {}", index, entry.en_word, e))?;
+    }
+
+    Ok(entries)
+}
+
+/// シードする語彙を決める。`path` (`VOCABULARY_SEED_FILE`) があればその JSON を読み込んで検証し、
+/// なければ組み込みの 5 語を使う。再コンパイルせずに独自の単語リストを配布できるようにするため。
+/// `count` (`SEED_VOCABULARY_COUNT`) が件数より大きければ、合成した単語で補う (`pad_seed_entries`)。
+/// `ApiJson` を通らないので、ここで `Normalize` を適用してから返す (DB 層は正規化済みの値をそのまま保存する)。
+pub fn load_seed_entries(path: Option<&Path>, count: Option<usize>) -> Result<Vec<CreateVocabularyRequest>, String> {
+    let entries = match path {
+        None => default_seed_entries(),
+        Some(path) => {
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+            let entries = parse_seed_entries(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+            tracing::info!("Loaded {} vocabulary seed entries from {}", entries.len(), path.display());
+            entries
+        }
+    };
+
+    let mut entries = match count {
+        Some(count) => pad_seed_entries(entries, count),
+        None => entries,
+    };
+    entries.iter_mut().for_each(Normalize::normalize);
+    Ok(entries)
+}
+
+/// 合成したシード語彙に付けるタグ。負荷試験の後に `?tag=synthetic` で見分けられるようにする。
+pub const SYNTHETIC_SEED_TAG: &str = "synthetic";
+
+/// `entries` が `count` 件に満たなければ、`word_00001` のような合成語を末尾に足して `count` 件にする。
+/// 負荷試験やベンチマーク用。`count` 以上あるときはそのまま返す (切り詰めはしない)。
+pub fn pad_seed_entries(mut entries: Vec<CreateVocabularyRequest>, count: usize) -> Vec<CreateVocabularyRequest> {
+    let missing = count.saturating_sub(entries.len());
+    entries.extend((1..=missing).map(|n| CreateVocabularyRequest {
+        en_word: format!("word_{:05}", n),
+        ja_word: format!("単語_{:05}", n),
+        en_example: Some(format!("This is synthetic word number {}.", n)),
+        ja_example: Some(format!("これは {} 番目の合成語です。", n)),
+        lang: None,
+        tags: Some(vec![SYNTHETIC_SEED_TAG.to_string()]),
+    }));
+    entries
+}
+
+/// `GET /api/vocabulary/random` で一度に取得できる最大件数。
+pub const MAX_RANDOM_COUNT: i64 = 50;
+
+/// `GET /api/vocabulary/random?exclude=` で指定できる ID の最大数。
+pub const MAX_RANDOM_EXCLUDE_IDS: usize = 200;
+
+/// `GET /api/vocabulary/random` のクエリパラメータ。
+/// `count` を省略した場合は従来どおり 1 件 (単一オブジェクト) を返す。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RandomVocabularyParams {
+    /// 取得件数。上限を超えた値は丸められ、省略時は単一オブジェクトを返す
+    pub count: Option<i64>,
+    /// 出題済みなど、避けたい語彙 ID のカンマ区切り (例: `1,2,3`)。最大 200 件。
+    /// 避けると足りない場合は除外した語彙からも選ぶ
+    pub exclude: Option<String>,
+    /// 指定すると、同じシードには (語彙が変わらない限り) 同じ順序で返す。テストや A/B 用。省略時は毎回ランダム。
+    /// シードを指定したときは `X-Session-Id` による直近の出題の回避を行わない
+    pub seed: Option<u64>,
+}
+
+impl RandomVocabularyParams {
+    /// 取得件数を決定する。1 未満はエラー、上限を超えた場合は `MAX_RANDOM_COUNT` に丸める。
+    pub fn resolved_count(&self) -> Result<i64, String> {
+        match self.count {
+            None => Ok(1),
+            Some(count) if count < 1 => Err("count must be at least 1".to_string()),
+            Some(count) => Ok(count.min(MAX_RANDOM_COUNT)),
+        }
+    }
+
+    /// 要求された `count` が上限に丸められたかどうか。
+    pub fn is_clamped(&self) -> bool {
+        self.count.is_some_and(|count| count > MAX_RANDOM_COUNT)
+    }
+
+    /// `exclude` を語彙 ID の列に変換する。未指定・空なら空の列。
+    pub fn exclude_ids(&self) -> Result<Vec<i32>, String> {
+        parse_id_list("exclude", self.exclude.as_deref().unwrap_or_default(), MAX_RANDOM_EXCLUDE_IDS)
+    }
+
+    /// 後方互換のため、配列ではなく単一オブジェクトで返すべきかどうか。
+    pub fn wants_single(&self) -> bool {
+        matches!(self.count, None | Some(1))
+    }
+}
+
+/// `GET /api/vocabulary/:id/related` の既定の件数。
+pub const DEFAULT_RELATED_LIMIT: i64 = 5;
+
+/// `GET /api/vocabulary/:id/related` で一度に取得できる最大件数。
+pub const MAX_RELATED_LIMIT: i64 = 50;
+
+/// 似た語彙とみなす `en_word` のトライグラム類似度 (`similarity`) の下限。これより大きいものだけを返す。
+pub const RELATED_SIMILARITY_THRESHOLD: f32 = 0.3;
+
+/// `GET /api/vocabulary/:id/related` のクエリパラメータ。
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RelatedVocabularyParams {
+    /// 取得件数。省略時は 5 件、上限を超えた値は丸められる
+    pub limit: Option<i64>,
+}
+
+impl RelatedVocabularyParams {
+    /// 取得件数を決定する。1 未満はエラー、上限を超えた場合は `MAX_RELATED_LIMIT` に丸める。
+    pub fn resolved_limit(&self) -> Result<i64, String> {
+        match self.limit {
+            None => Ok(DEFAULT_RELATED_LIMIT),
+            Some(limit) if limit < 1 => Err("limit must be at least 1".to_string()),
+            Some(limit) => Ok(limit.min(MAX_RELATED_LIMIT)),
+        }
+    }
+
+    /// 要求された `limit` が上限に丸められたかどうか。
+    pub fn is_clamped(&self) -> bool {
+        self.limit.is_some_and(|limit| limit > MAX_RELATED_LIMIT)
+    }
+}
+
+impl VocabularyListParams {
+    /// `fields` を解釈する。未知のフィールド名はエラー。
+    pub fn field_selection(&self) -> Result<FieldSelection, String> {
+        FieldSelection::parse(self.fields.as_deref(), VOCABULARY_FIELDS)
+    }
+
+    /// `id_as_string` に対応する `id` の型。
+    pub fn id_format(&self) -> IdFormat {
+        IdFormat::from_flag(self.id_as_string)
+    }
+
+    /// `sort` を許可リストの列名に変換する。未指定時は `created_at`。
+    pub fn sort_column(&self) -> Result<&'static str, String> {
+        match self.sort.as_deref().map(str::trim) {
+            None | Some("") | Some("created_at") => Ok("created_at"),
+            Some("en_word") => Ok("en_word"),
+            Some("ja_word") => Ok("ja_word"),
+            Some(other) => Err(format!(
+                "Invalid sort key '{}'. Must be one of: created_at, en_word, ja_word",
+                other
+            )),
+        }
+    }
+
+    /// `order` を `ASC`/`DESC` に変換する。未指定時は従来どおり `DESC`。
+    pub fn sort_direction(&self) -> Result<&'static str, String> {
+        match self.order.as_deref().map(|o| o.trim().to_lowercase()) {
+            None => Ok("DESC"),
+            Some(order) => match order.as_str() {
+                "" | "desc" => Ok("DESC"),
+                "asc" => Ok("ASC"),
+                _ => Err(format!("Invalid order '{}'. Must be one of: asc, desc", order)),
+            },
+        }
+    }
+
+    /// `starts_with` を `ILIKE` 用の前方一致パターンに変換する。
+    /// `%`/`_`/`\` はエスケープし、ユーザー入力がワイルドカードとして解釈されないようにする。
+    pub fn starts_with_pattern(&self) -> Option<String> {
+        self.starts_with
+            .as_ref()
+            .map(|prefix| prefix.trim())
+            .filter(|prefix| !prefix.is_empty())
+            .map(|prefix| {
+                let escaped = prefix
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                format!("{}%", escaped)
+            })
+    }
+
+    /// `lang` を検証済みの言語コードに変換する。未指定・空なら絞り込まない。
+    pub fn lang_filter(&self) -> Result<Option<String>, String> {
+        self.lang
+            .as_deref()
+            .filter(|lang| !lang.trim().is_empty())
+            .map(parse_lang)
+            .transpose()
+    }
+
+    /// `tag` をタグの保存形式 (トリム・小文字) に揃える。未指定・空なら絞り込まない。
+    pub fn tag_filter(&self) -> Option<String> {
+        self.tag
+            .as_deref()
+            .map(normalize_tag)
+            .filter(|tag| !tag.is_empty())
+    }
+}
+
+/// タグをトリムして小文字にする。`Food` と `food` を同じタグとして扱うため。
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// `GET /api/vocabulary/tags` の要素。タグと、そのタグが付いた語彙の数。
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// 索引で英字 (a–z) 以外で始まる語彙をまとめるグループ名。
+pub const INDEX_OTHER_GROUP: &str = "#";
+
+/// `GET /api/vocabulary/index` の要素。頭文字 (小文字) と、その文字で始まる語彙の数。
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LetterCount {
+    pub letter: String,
+    pub count: i64,
+}
+
+/// 頭文字ごとの件数を A–Z の索引にまとめる。ASCII の英字以外 (`é` や数字、記号など) は `#` に合算する。
+/// 結果は `letter` の昇順なので、`#` は `a` より前に来る。
+pub fn vocabulary_index(rows: impl IntoIterator<Item = (String, i64)>) -> Vec<LetterCount> {
+    let mut counts: BTreeMap<String, i64> = BTreeMap::new();
+    for (letter, count) in rows {
+        let letter = if letter.len() == 1 && letter.as_bytes()[0].is_ascii_lowercase() {
+            letter
+        } else {
+            INDEX_OTHER_GROUP.to_string()
+        };
+        *counts.entry(letter).or_default() += count;
+    }
+    counts.into_iter().map(|(letter, count)| LetterCount { letter, count }).collect()
+}
+
+impl CreateVocabularyRequest {
+    /// 単語・和訳の必須チェックと長さ制限を行う。
+    /// 例文は任意だが、上限 1000 文字を超えた場合はエラーにする。
+    pub fn validate(&self) -> Result<(), String> {
+        validate_word(&self.en_word, "English word")?;
+        validate_word(&self.ja_word, "Japanese word")?;
+
+        if let Some(ref lang) = self.lang {
+            parse_lang(lang)?;
+        }
+
+        if let Some(ref tags) = self.tags {
+            validate_tags(tags)?;
+        }
+
+        if let Some(ref example) = self.en_example {
+            validate_example(example, "English example")?;
+        }
         if let Some(ref example) = self.ja_example {
-            if example.len() > 1000 {
-                return Err("Japanese example cannot exceed 1000 characters".to_string());
-            }
+            validate_example(example, "Japanese example")?;
         }
 
         Ok(())
     }
+}
+
+/// `POST /api/vocabulary/:id/favorite` の入力。
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FavoriteRequest {
+    /// `true` でスターを付け、`false` で外す
+    pub favorite: bool,
+    /// 付け外ししたユーザー。指定すると `favorites` に記録し、`GET /api/vocabulary/:id/favorited-by` に載る。
+    /// このとき `favorite` の値は、誰か 1 人でもお気に入りにしているかで決まる
+    #[serde(default)]
+    pub user_id: Option<uuid::Uuid>,
+}
+
+impl Normalize for CreateVocabularyRequest {
+    /// 英単語の空白をまとめ、和訳をトリムし、空白だけの例文は `None` にする。言語コードとタグは小文字に揃える。
+    /// 取り込み元ごとの表記揺れで重複行ができないようにするため。DB 層はこの値をそのまま保存する。
+    /// タグの重複は `validate()` の個数制限を変えないよう、ここでは除かない (保存時に `dedupe_tags` で除く)。
+    fn normalize(&mut self) {
+        normalize::collapse_whitespace(&mut self.en_word);
+        normalize::trim(&mut self.ja_word);
+        normalize::trim_optional(&mut self.en_example);
+        normalize::trim_optional(&mut self.ja_example);
+        if let Some(lang) = self.lang.as_mut() {
+            normalize::trim_lowercase(lang);
+        }
+        if let Some(tags) = self.tags.as_mut() {
+            tags.iter_mut().for_each(normalize::trim_lowercase);
+        }
+    }
+}
 
-    /// 英単語をトリムして返す。
-    pub fn get_normalized_en_word(&self) -> String {
-        self.en_word.trim().to_string()
+/// 真偽値と UUID だけなので正規化するものはない。
+impl Normalize for FavoriteRequest {
+    fn normalize(&mut self) {}
+}
+
+impl Normalize for UpdateVocabularyRequest {
+    /// 送られたフィールドだけを `CreateVocabularyRequest` と同じ規則で正規化する。
+    /// 空白だけの例文は `Some(None)` (例文を消す) になる。
+    fn normalize(&mut self) {
+        if let Some(en_word) = self.en_word.as_mut() {
+            normalize::collapse_whitespace(en_word);
+        }
+        if let Some(ja_word) = self.ja_word.as_mut() {
+            normalize::trim(ja_word);
+        }
+        if let Some(example) = self.en_example.as_mut() {
+            normalize::trim_optional(example);
+        }
+        if let Some(example) = self.ja_example.as_mut() {
+            normalize::trim_optional(example);
+        }
+        if let Some(tags) = self.tags.as_mut() {
+            tags.iter_mut().for_each(normalize::trim_lowercase);
+        }
     }
+}
+
+impl UpdateVocabularyRequest {
+    /// 少なくとも 1 フィールドを要求し、指定されたものには作成時と同じ制限をかける。
+    pub fn validate(&self) -> Result<(), String> {
+        if self.en_word.is_none()
+            && self.ja_word.is_none()
+            && self.en_example.is_none()
+            && self.ja_example.is_none()
+            && self.tags.is_none()
+        {
+            return Err(
+                "At least one field (en_word, ja_word, en_example, ja_example or tags) must be provided for update"
+                    .to_string(),
+            );
+        }
 
-    /// 和訳をトリムして返す。
-    pub fn get_normalized_ja_word(&self) -> String {
-        self.ja_word.trim().to_string()
+        if let Some(ref en_word) = self.en_word {
+            validate_word(en_word, "English word")?;
+        }
+        if let Some(ref ja_word) = self.ja_word {
+            validate_word(ja_word, "Japanese word")?;
+        }
+
+        if let Some(ref tags) = self.tags {
+            validate_tags(tags)?;
+        }
+
+        if let Some(Some(ref example)) = self.en_example {
+            validate_example(example, "English example")?;
+        }
+        if let Some(Some(ref example)) = self.ja_example {
+            validate_example(example, "Japanese example")?;
+        }
+
+        Ok(())
     }
+}
 
-    /// 英文例をトリムし、空文字なら `None` にする。
-    pub fn get_normalized_en_example(&self) -> Option<String> {
-        self.en_example
-            .as_ref()
-            .map(|e| e.trim().to_string())
-            .filter(|e| !e.is_empty())
+/// 英単語・和訳の必須チェックと長さ制限。作成と更新で同じ規則を使う。`field` はエラーメッセージに入る名前。
+fn validate_word(word: &str, field: &str) -> Result<(), String> {
+    if word.trim().is_empty() {
+        return Err(format!("{} cannot be empty", field));
+    }
+    if word.len() > MAX_WORD_LEN {
+        return Err(format!("{} cannot exceed {} characters", field, MAX_WORD_LEN));
     }
+    Ok(())
+}
 
-    /// 和文例も同様にトリム＋空判定を行う。
-    pub fn get_normalized_ja_example(&self) -> Option<String> {
-        self.ja_example
-            .as_ref()
-            .map(|e| e.trim().to_string())
-            .filter(|e| !e.is_empty())
+/// 例文の長さ制限。空の例文は正規化で `None` になっているので、ここでは長さだけを見る。
+fn validate_example(example: &str, field: &str) -> Result<(), String> {
+    if example.len() > MAX_EXAMPLE_LEN {
+        return Err(format!("{} cannot exceed {} characters", field, MAX_EXAMPLE_LEN));
     }
+    Ok(())
+}
+
+/// タグの個数と各タグの長さを検証する。
+fn validate_tags(tags: &[String]) -> Result<(), String> {
+    if tags.len() > MAX_TAGS {
+        return Err(format!("Cannot have more than {} tags", MAX_TAGS));
+    }
+    for tag in tags {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err("Tags cannot be empty".to_string());
+        }
+        if tag.chars().count() > MAX_TAG_CHARS {
+            return Err(format!("Tag cannot exceed {} characters", MAX_TAG_CHARS));
+        }
+    }
+    Ok(())
+}
+
+/// 正規化済みのタグから重複を除く (最初の出現順を保つ)。
+pub fn dedupe_tags(tags: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.into_iter().filter(|tag| seen.insert(tag.clone())).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::fields::WithIdFormat;
+
+    #[test]
+    fn test_changes_since_defaults_to_epoch() {
+        let params = VocabularyChangesParams::default();
+        assert_eq!(params.since().unwrap(), DateTime::<Utc>::UNIX_EPOCH);
+
+        let params = VocabularyChangesParams { since: Some("2024-01-31T09:00:00+09:00".to_string()) };
+        assert_eq!(params.since().unwrap().to_rfc3339(), "2024-01-31T00:00:00+00:00");
+
+        let params = VocabularyChangesParams { since: Some("yesterday".to_string()) };
+        assert!(params.since().unwrap_err().contains("RFC3339"));
+    }
+
+    fn update(json: &str) -> UpdateVocabularyRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_update_en_example_absent_null_and_value() {
+        let normalized = |json: &str| {
+            let mut request = update(json);
+            request.normalize();
+            request
+        };
+
+        let absent = normalized(r#"{"ja_word": "りんご"}"#);
+        assert_eq!(absent.en_example, None);
+        assert!(absent.validate().is_ok());
+
+        let cleared = normalized(r#"{"en_example": null}"#);
+        assert_eq!(cleared.en_example, Some(None));
+        assert!(cleared.validate().is_ok());
+        // A blank example clears it too, as on create
+        assert_eq!(normalized(r#"{"en_example": "  "}"#).en_example, Some(None));
+
+        let set = normalized(r#"{"en_example": " An apple a day. "}"#);
+        assert_eq!(set.en_example, Some(Some("An apple a day.".to_string())));
+        assert_eq!(set.ja_example, None);
+    }
+
+    #[test]
+    fn test_update_vocabulary_request_validation() {
+        assert!(update("{}").validate().unwrap_err().contains("At least one field"));
+        assert_eq!(update(r#"{"en_word": " "}"#).validate(), Err("English word cannot be empty".to_string()));
+        assert!(update(r#"{"tags": []}"#).validate().is_ok());
+
+        let err = serde_json::from_str::<UpdateVocabularyRequest>(r#"{"lang": "fr"}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `lang`"));
+    }
+
+    #[test]
+    fn test_create_and_update_share_field_rules() {
+        let long_word = "a".repeat(MAX_WORD_LEN + 1);
+        let long_example = "a".repeat(MAX_EXAMPLE_LEN + 1);
+        let cases = [
+            ("en_word", long_word.as_str(), "English word cannot exceed 200 characters"),
+            ("ja_word", " ", "Japanese word cannot be empty"),
+            ("ja_word", long_word.as_str(), "Japanese word cannot exceed 200 characters"),
+            ("en_example", long_example.as_str(), "English example cannot exceed 1000 characters"),
+            ("ja_example", long_example.as_str(), "Japanese example cannot exceed 1000 characters"),
+        ];
+        for (field, value, message) in cases {
+            let mut create = serde_json::json!({ "en_word": "apple", "ja_word": "りんご" });
+            create[field] = serde_json::json!(value);
+            let create: CreateVocabularyRequest = serde_json::from_value(create).unwrap();
+            assert_eq!(create.validate(), Err(message.to_string()), "create {}", field);
+
+            let update: UpdateVocabularyRequest = serde_json::from_value(serde_json::json!({ field: value })).unwrap();
+            assert_eq!(update.validate(), Err(message.to_string()), "update {}", field);
+        }
+    }
+
+    #[test]
+    fn test_favorite_request_requires_the_flag() {
+        let request = serde_json::from_str::<FavoriteRequest>(r#"{"favorite": true}"#).unwrap();
+        assert!(request.favorite);
+        assert_eq!(request.user_id, None);
+
+        assert!(serde_json::from_str::<FavoriteRequest>("{}").is_err());
+        assert!(serde_json::from_str::<FavoriteRequest>(r#"{"favorite": true, "note": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_favorite_request_accepts_a_user_id() {
+        let request = serde_json::from_str::<FavoriteRequest>(
+            r#"{"favorite": false, "user_id": "550e8400-e29b-41d4-a716-446655440000"}"#,
+        )
+        .unwrap();
+        assert!(!request.favorite);
+        assert_eq!(request.user_id.unwrap().to_string(), "550e8400-e29b-41d4-a716-446655440000");
+
+        assert!(serde_json::from_str::<FavoriteRequest>(r#"{"favorite": true, "user_id": 1}"#).is_err());
+        assert!(serde_json::from_str::<FavoriteRequest>(r#"{"favorite": true, "user_id": "not-a-uuid"}"#).is_err());
+    }
 
     #[test]
     fn test_create_vocabulary_request_validation() {
@@ -102,6 +1063,8 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: Some("Hello, how are you?".to_string()),
             ja_example: Some("こんにちは、お元気ですか？".to_string()),
+            lang: None,
+            tags: None,
         };
         assert!(valid_request.validate().is_ok());
 
@@ -111,6 +1074,8 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: None,
             ja_example: None,
+            lang: None,
+            tags: None,
         };
         assert!(valid_request_no_examples.validate().is_ok());
 
@@ -120,6 +1085,8 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: None,
             ja_example: None,
+            lang: None,
+            tags: None,
         };
         assert!(invalid_en_word.validate().is_err());
 
@@ -129,6 +1096,8 @@ mod tests {
             ja_word: "".to_string(),
             en_example: None,
             ja_example: None,
+            lang: None,
+            tags: None,
         };
         assert!(invalid_ja_word.validate().is_err());
 
@@ -138,6 +1107,8 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: None,
             ja_example: None,
+            lang: None,
+            tags: None,
         };
         assert!(long_en_word.validate().is_err());
 
@@ -147,6 +1118,8 @@ mod tests {
             ja_word: "あ".repeat(201),
             en_example: None,
             ja_example: None,
+            lang: None,
+            tags: None,
         };
         assert!(long_ja_word.validate().is_err());
 
@@ -156,6 +1129,8 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: Some("a".repeat(1001)),
             ja_example: None,
+            lang: None,
+            tags: None,
         };
         assert!(long_en_example.validate().is_err());
 
@@ -165,23 +1140,273 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: None,
             ja_example: Some("あ".repeat(1001)),
+            lang: None,
+            tags: None,
         };
         assert!(long_ja_example.validate().is_err());
     }
 
     #[test]
     fn test_create_vocabulary_request_normalization() {
-        let request = CreateVocabularyRequest {
+        let mut request = CreateVocabularyRequest {
             en_word: "  hello  ".to_string(),
             ja_word: "  こんにちは  ".to_string(),
             en_example: Some("  Hello, how are you?  ".to_string()),
             ja_example: Some("   ".to_string()), // Only whitespace
+            lang: None,
+            tags: None,
         };
+        request.normalize();
         
-        assert_eq!(request.get_normalized_en_word(), "hello");
-        assert_eq!(request.get_normalized_ja_word(), "こんにちは");
-        assert_eq!(request.get_normalized_en_example(), Some("Hello, how are you?".to_string()));
-        assert_eq!(request.get_normalized_ja_example(), None); // Empty should be None
+        assert_eq!(request.en_word, "hello");
+
+        let mut phrase = CreateVocabularyRequest {
+            en_word: " look \t  up ".to_string(),
+            ja_word: "調べる".to_string(),
+            en_example: None,
+            ja_example: None,
+            lang: None,
+            tags: None,
+        };
+        phrase.normalize();
+        assert_eq!(phrase.en_word, "look up");
+        assert_eq!(request.ja_word, "こんにちは");
+        assert_eq!(request.en_example, Some("Hello, how are you?".to_string()));
+        assert_eq!(request.ja_example, None); // Empty should be None
+    }
+
+    #[test]
+    fn test_vocabulary_list_params_sort_allowlist() {
+        let default_params = VocabularyListParams::default();
+        assert_eq!(default_params.sort_column(), Ok("created_at"));
+        assert_eq!(default_params.sort_direction(), Ok("DESC"));
+
+        let params = VocabularyListParams {
+            sort: Some("en_word".to_string()),
+            order: Some("ASC".to_string()),
+            starts_with: None,
+            ..Default::default()
+        };
+        assert_eq!(params.sort_column(), Ok("en_word"));
+        assert_eq!(params.sort_direction(), Ok("ASC"));
+
+        // Raw column names or SQL fragments must never be accepted
+        let invalid_sort = VocabularyListParams {
+            sort: Some("id; DROP TABLE vocabulary".to_string()),
+            order: None,
+            starts_with: None,
+            ..Default::default()
+        };
+        assert!(invalid_sort.sort_column().is_err());
+
+        let invalid_order = VocabularyListParams {
+            sort: None,
+            order: Some("sideways".to_string()),
+            starts_with: None,
+            ..Default::default()
+        };
+        assert!(invalid_order.sort_direction().is_err());
+    }
+
+    #[test]
+    fn test_vocabulary_list_params_starts_with_pattern() {
+        let params = VocabularyListParams {
+            sort: None,
+            order: None,
+            starts_with: Some("  ap ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(params.starts_with_pattern(), Some("ap%".to_string()));
+
+        // Wildcards in user input are escaped
+        let wildcard = VocabularyListParams {
+            sort: None,
+            order: None,
+            starts_with: Some("a%_".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(wildcard.starts_with_pattern(), Some("a\\%\\_%".to_string()));
+
+        let blank = VocabularyListParams {
+            sort: None,
+            order: None,
+            starts_with: Some("   ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(blank.starts_with_pattern(), None);
+    }
+
+    #[test]
+    fn test_lang_defaults_to_ja_and_is_allowlisted() {
+        let mut request = CreateVocabularyRequest {
+            en_word: "apple".to_string(),
+            ja_word: "manzana".to_string(),
+            en_example: None,
+            ja_example: None,
+            lang: None,
+            tags: None,
+        };
+        assert!(request.validate().is_ok());
+        request.normalize();
+        assert_eq!(request.lang, None); // Stored as DEFAULT_LANG ("ja")
+
+        request.lang = Some(" ES ".to_string());
+        assert!(request.validate().is_ok());
+        request.normalize();
+        assert_eq!(request.lang.as_deref(), Some("es"));
+
+        request.lang = Some("xx".to_string());
+        assert!(request.validate().unwrap_err().contains("Invalid lang 'xx'"));
+
+        let parsed: CreateVocabularyRequest =
+            serde_json::from_str(r#"{"en_word":"apple","ja_word":"りんご","en_example":null,"ja_example":null}"#).unwrap();
+        assert_eq!(parsed.lang, None);
+    }
+
+    #[test]
+    fn test_tags_are_validated_and_normalized() {
+        let mut request = CreateVocabularyRequest {
+            en_word: "apple".to_string(),
+            ja_word: "りんご".to_string(),
+            en_example: None,
+            ja_example: None,
+            lang: None,
+            tags: Some(vec![" Food ".to_string(), "fruit".to_string(), "food".to_string()]),
+        };
+        assert!(request.validate().is_ok());
+        request.normalize();
+        assert_eq!(request.tags.clone().map(dedupe_tags), Some(vec!["food".to_string(), "fruit".to_string()]));
+
+        request.tags = Some(vec!["tag".to_string(); MAX_TAGS + 1]);
+        assert!(request.validate().is_err());
+
+        request.tags = Some(vec!["あ".repeat(MAX_TAG_CHARS)]);
+        assert!(request.validate().is_ok());
+        request.tags = Some(vec!["あ".repeat(MAX_TAG_CHARS + 1)]);
+        assert!(request.validate().is_err());
+
+        request.tags = Some(vec!["  ".to_string()]);
+        assert_eq!(request.validate(), Err("Tags cannot be empty".to_string()));
+
+    }
+
+    #[test]
+    fn test_vocabulary_list_params_tag_filter() {
+        let params = VocabularyListParams { tag: Some(" Business ".to_string()), ..Default::default() };
+        assert_eq!(params.tag_filter(), Some("business".to_string()));
+
+        let blank = VocabularyListParams { tag: Some(String::new()), ..Default::default() };
+        assert_eq!(blank.tag_filter(), None);
+    }
+
+    #[test]
+    fn test_vocabulary_list_params_lang_filter() {
+        let none = VocabularyListParams::default();
+        assert_eq!(none.lang_filter(), Ok(None));
+
+        let korean = VocabularyListParams { lang: Some("KO".to_string()), ..Default::default() };
+        assert_eq!(korean.lang_filter(), Ok(Some("ko".to_string())));
+
+        let unknown = VocabularyListParams { lang: Some("klingon".to_string()), ..Default::default() };
+        assert!(unknown.lang_filter().is_err());
+    }
+
+    #[test]
+    fn test_random_vocabulary_params_count() {
+        let default_params = RandomVocabularyParams::default();
+        assert_eq!(default_params.resolved_count(), Ok(1));
+        assert!(default_params.wants_single());
+
+        let five = RandomVocabularyParams { count: Some(5), ..Default::default() };
+        assert_eq!(five.resolved_count(), Ok(5));
+        assert!(!five.wants_single());
+
+        let too_many = RandomVocabularyParams { count: Some(1000), ..Default::default() };
+        assert_eq!(too_many.resolved_count(), Ok(MAX_RANDOM_COUNT));
+        assert!(too_many.is_clamped());
+        assert!(!five.is_clamped());
+
+        let zero = RandomVocabularyParams { count: Some(0), ..Default::default() };
+        assert!(zero.resolved_count().is_err());
+    }
+
+    #[test]
+    fn test_coverage_request_tokenize() {
+        let request = CoverageRequest {
+            text: "I eat an Apple. The apple isn't a well-known book!".to_string(),
+        };
+
+        assert_eq!(
+            request.tokenize(),
+            vec!["i", "eat", "an", "apple", "the", "isn't", "a", "well-known", "book"]
+        );
+    }
+
+    #[test]
+    fn test_coverage_request_validation() {
+        assert!(CoverageRequest { text: "hello".to_string() }.validate().is_ok());
+        assert!(CoverageRequest { text: "   ".to_string() }.validate().is_err());
+        assert!(CoverageRequest { text: "a".repeat(MAX_COVERAGE_TEXT_CHARS + 1) }.validate().is_err());
+    }
+
+    #[test]
+    fn test_coverage_response_from_matches() {
+        let words = vec!["apple".to_string(), "is".to_string(), "book".to_string(), "red".to_string()];
+        let matches = HashMap::from([("apple".to_string(), 1), ("book".to_string(), 2)]);
+
+        let response = CoverageResponse::from_matches(words, &matches);
+
+        assert_eq!(
+            response.known,
+            vec![
+                KnownWord { word: "apple".to_string(), id: 1 },
+                KnownWord { word: "book".to_string(), id: 2 },
+            ]
+        );
+        assert_eq!(response.unknown, vec!["is".to_string(), "red".to_string()]);
+        assert_eq!(response.coverage_ratio, 0.5);
+    }
+
+    #[test]
+    fn test_difficulty_heuristic_word_lengths() {
+        let heuristic: DifficultyHeuristic = "4,6,8,10".parse().unwrap();
+
+        assert_eq!(heuristic.level_for("a"), 1);
+        assert_eq!(heuristic.level_for("book"), 1);
+        assert_eq!(heuristic.level_for("apple"), 2);
+        assert_eq!(heuristic.level_for("friend"), 2);
+        assert_eq!(heuristic.level_for("computer"), 3);
+        assert_eq!(heuristic.level_for("vocabulary"), 4);
+        assert_eq!(heuristic.level_for("extraordinary"), 5);
+        // Length is counted in characters, not bytes
+        assert_eq!(heuristic.level_for("café"), 1);
+        assert_eq!(heuristic.max_level(), 5);
+    }
+
+    #[test]
+    fn test_difficulty_heuristic_case_expression() {
+        let heuristic = DifficultyHeuristic::new(vec![4, 8]).unwrap();
+        assert_eq!(
+            heuristic.case_expression("en_word"),
+            "CASE WHEN char_length(en_word) <= 4 THEN 1 WHEN char_length(en_word) <= 8 THEN 2 ELSE 3 END"
+        );
+    }
+
+    #[test]
+    fn test_difficulty_heuristic_rejects_invalid_thresholds() {
+        assert!("".parse::<DifficultyHeuristic>().is_err());
+        assert!("4,x".parse::<DifficultyHeuristic>().is_err());
+        assert!("0,4".parse::<DifficultyHeuristic>().is_err());
+        assert!("6,4".parse::<DifficultyHeuristic>().is_err());
+        assert!("4,4".parse::<DifficultyHeuristic>().is_err());
+    }
+
+    #[test]
+    fn test_validate_vocabulary_id() {
+        assert_eq!(validate_vocabulary_id(1), Ok(1));
+        assert_eq!(validate_vocabulary_id(i32::MAX), Ok(i32::MAX));
+        assert!(validate_vocabulary_id(0).is_err());
+        assert!(validate_vocabulary_id(-5).is_err());
     }
 
     #[test]
@@ -192,13 +1417,18 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: Some("Hello, how are you?".to_string()),
             ja_example: Some("こんにちは、お元気ですか？".to_string()),
+            lang: DEFAULT_LANG.to_string(),
+            tags: Vec::new(),
+            difficulty: 1,
+            favorite: false,
+            review: ReviewSchedule::default(),
             created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
         };
 
         // Test serialization to JSON
         let json = serde_json::to_string(&vocabulary).expect("Failed to serialize vocabulary");
-        let expected = r#"{"id":1,"en_word":"hello","ja_word":"こんにちは","en_example":"Hello, how are you?","ja_example":"こんにちは、お元気ですか？","created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
+        let expected = r#"{"id":1,"en_word":"hello","ja_word":"こんにちは","en_example":"Hello, how are you?","ja_example":"こんにちは、お元気ですか？","lang":"ja","tags":[],"difficulty":1,"favorite":false,"review_count":0,"ease_factor":2.5,"next_review_at":null,"last_reviewed_at":null,"created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
         assert_eq!(json, expected);
     }
 
@@ -210,16 +1440,234 @@ mod tests {
             ja_word: "こんにちは".to_string(),
             en_example: None,
             ja_example: None,
+            lang: DEFAULT_LANG.to_string(),
+            tags: Vec::new(),
+            difficulty: DEFAULT_DIFFICULTY,
+            favorite: false,
+            review: ReviewSchedule::default(),
             created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
             updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
         };
 
         // Test serialization to JSON with null examples
         let json = serde_json::to_string(&vocabulary).expect("Failed to serialize vocabulary");
-        let expected = r#"{"id":1,"en_word":"hello","ja_word":"こんにちは","en_example":null,"ja_example":null,"created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
+        let expected = r#"{"id":1,"en_word":"hello","ja_word":"こんにちは","en_example":null,"ja_example":null,"lang":"ja","tags":[],"difficulty":0,"favorite":false,"review_count":0,"ease_factor":2.5,"next_review_at":null,"last_reviewed_at":null,"created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
         assert_eq!(json, expected);
     }
 
+    #[test]
+    fn test_vocabulary_batch_params_parse_ids() {
+        let params = VocabularyBatchParams { ids: Some(" 3, 1,,3 ,2".to_string()) };
+        assert_eq!(params.parse_ids(), Ok(vec![3, 1, 2]));
+
+        for invalid in [None, Some(""), Some("1,a"), Some("1.5"), Some("0"), Some("-2")] {
+            let params = VocabularyBatchParams { ids: invalid.map(str::to_string) };
+            assert!(params.parse_ids().is_err(), "{:?} should be rejected", invalid);
+        }
+
+        let too_many = (1..=MAX_BATCH_IDS as i32 + 1).map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+        assert!(VocabularyBatchParams { ids: Some(too_many) }.parse_ids().is_err());
+    }
+
+    #[test]
+    fn test_related_vocabulary_limit() {
+        assert_eq!(RelatedVocabularyParams::default().resolved_limit(), Ok(DEFAULT_RELATED_LIMIT));
+        assert!(RelatedVocabularyParams { limit: Some(0) }.resolved_limit().is_err());
+
+        let too_many = RelatedVocabularyParams { limit: Some(MAX_RELATED_LIMIT + 1) };
+        assert_eq!(too_many.resolved_limit(), Ok(MAX_RELATED_LIMIT));
+        assert!(too_many.is_clamped());
+        assert!(!RelatedVocabularyParams { limit: Some(MAX_RELATED_LIMIT) }.is_clamped());
+    }
+
+    #[test]
+    fn test_random_vocabulary_params_exclude_ids() {
+        let params = RandomVocabularyParams { exclude: Some("4, 2,,4".to_string()), ..Default::default() };
+        assert_eq!(params.exclude_ids(), Ok(vec![4, 2]));
+
+        assert_eq!(RandomVocabularyParams::default().exclude_ids(), Ok(vec![]));
+        assert_eq!(RandomVocabularyParams { exclude: Some(String::new()), ..Default::default() }.exclude_ids(), Ok(vec![]));
+
+        let invalid = RandomVocabularyParams { exclude: Some("1,x".to_string()), ..Default::default() };
+        assert_eq!(invalid.exclude_ids(), Err("exclude must be comma-separated integers, got 'x'".to_string()));
+
+        let too_many = (1..=MAX_RANDOM_EXCLUDE_IDS as i32 + 1).map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+        assert!(RandomVocabularyParams { exclude: Some(too_many), ..Default::default() }.exclude_ids().is_err());
+    }
+
+    #[test]
+    fn test_vocabulary_batch_response_keeps_request_order() {
+        let entry = |id: i32| Vocabulary {
+            id,
+            en_word: format!("word{}", id),
+            ja_word: format!("単語{}", id),
+            en_example: None,
+            ja_example: None,
+            lang: DEFAULT_LANG.to_string(),
+            tags: Vec::new(),
+            difficulty: DEFAULT_DIFFICULTY,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let response = VocabularyBatchResponse::new(&[5, 2, 9, 1], vec![entry(1), entry(2), entry(5)]);
+        let ids: Vec<i32> = response.vocabulary.iter().map(|v| v.id).collect();
+        assert_eq!(ids, vec![5, 2, 1]);
+        assert_eq!(response.missing_ids, vec![9]);
+    }
+
+    fn id_format_entry(id: i32) -> Vocabulary {
+        Vocabulary {
+            id,
+            en_word: format!("word{}", id),
+            ja_word: format!("単語{}", id),
+            en_example: None,
+            ja_example: None,
+            lang: DEFAULT_LANG.to_string(),
+            tags: Vec::new(),
+            difficulty: DEFAULT_DIFFICULTY,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn with_string_ids<T: Serialize>(value: T) -> serde_json::Value {
+        serde_json::to_value(WithIdFormat(value, IdFormat::String)).unwrap()
+    }
+
+    #[test]
+    fn test_id_format_for_single_entry_responses() {
+        // GET/PATCH /api/vocabulary/:id, review and favorite
+        let entry = id_format_entry(7);
+        assert_eq!(serde_json::to_value(&entry).unwrap()["id"], serde_json::json!(7));
+        assert_eq!(serde_json::to_value(WithIdFormat(&entry, IdFormat::Number)).unwrap()["id"], serde_json::json!(7));
+        let json = with_string_ids(&entry);
+        assert_eq!(json["id"], serde_json::json!("7"));
+        assert_eq!(json["review_count"], serde_json::json!(0));
+
+        // POST /api/vocabulary flattens the entry next to `inserted`
+        let created = with_string_ids(CreateVocabularyResponse { vocabulary: entry, inserted: true });
+        assert_eq!(created["id"], serde_json::json!("7"));
+        assert_eq!(created["inserted"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn test_id_format_for_list_responses() {
+        // random, due, related, the list and the changes feed
+        let json = with_string_ids(vec![id_format_entry(1), id_format_entry(2)]);
+        assert_eq!(json[0]["id"], serde_json::json!("1"));
+        assert_eq!(json[1]["id"], serde_json::json!("2"));
+
+        let changes = VocabularyChanges {
+            server_time: DateTime::<Utc>::UNIX_EPOCH,
+            changed: vec![id_format_entry(3)],
+            deleted_ids: vec![4],
+        };
+        let json = with_string_ids(changes);
+        assert_eq!(json["changed"][0]["id"], serde_json::json!("3"));
+        assert_eq!(json["deleted_ids"], serde_json::json!(["4"]));
+    }
+
+    #[test]
+    fn test_id_format_for_batch_response() {
+        let response = VocabularyBatchResponse::new(&[2, 9], vec![id_format_entry(2)]);
+        let json = with_string_ids(&response);
+        assert_eq!(json["vocabulary"][0]["id"], serde_json::json!("2"));
+        assert_eq!(json["missing_ids"], serde_json::json!(["9"]));
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["missing_ids"], serde_json::json!([9]));
+    }
+
+    #[test]
+    fn test_id_format_for_history_entries() {
+        let entry = VocabularyHistoryEntry {
+            id: 11,
+            vocabulary_id: 7,
+            en_word: "apple".to_string(),
+            ja_word: "りんご".to_string(),
+            en_example: None,
+            ja_example: None,
+            tags: Vec::new(),
+            changed_at: DateTime::<Utc>::UNIX_EPOCH,
+        };
+        let json = with_string_ids(vec![&entry]);
+        assert_eq!(json[0]["vocabulary_id"], serde_json::json!("7"));
+        // The history row's own id isn't a vocabulary id and stays numeric
+        assert_eq!(json[0]["id"], serde_json::json!(11));
+    }
+
+    #[test]
+    fn test_vocabulary_examples_projection() {
+        let vocabulary = Vocabulary {
+            id: 1,
+            en_word: "hello".to_string(),
+            ja_word: "こんにちは".to_string(),
+            en_example: Some("Hello, how are you?".to_string()),
+            ja_example: None,
+            lang: DEFAULT_LANG.to_string(),
+            tags: Vec::new(),
+            difficulty: DEFAULT_DIFFICULTY,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
+        };
+
+        let json = serde_json::to_string(&VocabularyExamples::from(vocabulary)).unwrap();
+        assert_eq!(json, r#"{"en_example":"Hello, how are you?","ja_example":null}"#);
+    }
+
+    #[test]
+    fn test_vocabulary_history_entry_keeps_cleared_examples_as_null() {
+        let entry = VocabularyHistoryEntry {
+            id: 7,
+            vocabulary_id: 1,
+            en_word: "hello".to_string(),
+            ja_word: "こんにちは".to_string(),
+            en_example: None,
+            ja_example: None,
+            tags: vec!["greeting".to_string()],
+            changed_at: DateTime::parse_from_rfc3339("2022-01-02T00:00:00Z").unwrap().with_timezone(&Utc),
+        };
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["en_example"], serde_json::Value::Null);
+        assert_eq!(json["tags"], serde_json::json!(["greeting"]));
+        assert_eq!(json["changed_at"], "2022-01-02T00:00:00Z");
+    }
+
+    #[test]
+    fn test_create_vocabulary_response_reports_insert_or_update() {
+        let vocabulary = Vocabulary {
+            id: 1,
+            en_word: "hello".to_string(),
+            ja_word: "こんにちは".to_string(),
+            en_example: None,
+            ja_example: None,
+            lang: DEFAULT_LANG.to_string(),
+            tags: Vec::new(),
+            difficulty: DEFAULT_DIFFICULTY,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc),
+        };
+
+        let response = CreateVocabularyResponse { vocabulary, inserted: false };
+        let json = serde_json::to_value(&response).expect("Failed to serialize response");
+        assert_eq!(json["id"], 1);
+        assert_eq!(json["en_word"], "hello");
+        assert_eq!(json["inserted"], false);
+
+        let query: CreateVocabularyQuery = serde_json::from_str("{}").unwrap();
+        assert!(!query.upsert);
+    }
+
     #[test]
     fn test_vocabulary_deserialization() {
         let json = r#"{"id":1,"en_word":"hello","ja_word":"こんにちは","en_example":"Hello, how are you?","ja_example":"こんにちは、お元気ですか？","created_at":"2022-01-01T00:00:00Z","updated_at":"2022-01-01T00:00:00Z"}"#;
@@ -281,4 +1729,114 @@ mod tests {
         assert_eq!(request.en_example, None);
         assert_eq!(request.ja_example, None);
     }
+
+    #[test]
+    fn test_default_seed_entries_are_valid() {
+        let entries = default_seed_entries();
+        assert_eq!(entries.len(), 5);
+        assert!(entries.iter().all(|entry| entry.validate().is_ok()));
+    }
+
+    #[test]
+    fn test_vocabulary_fields_match_serialized_keys() {
+        let vocabulary = Vocabulary {
+            id: 1,
+            en_word: "apple".to_string(),
+            ja_word: "りんご".to_string(),
+            en_example: None,
+            ja_example: None,
+            lang: default_lang(),
+            tags: Vec::new(),
+            difficulty: DEFAULT_DIFFICULTY,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let serialized = serde_json::to_value(&vocabulary).unwrap();
+        let mut keys: Vec<&str> = serialized.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut fields = VOCABULARY_FIELDS.to_vec();
+        keys.sort_unstable();
+        fields.sort_unstable();
+        assert_eq!(keys, fields);
+    }
+
+    #[test]
+    fn test_list_fields_are_validated_against_vocabulary_fields() {
+        let params = VocabularyListParams { fields: Some("id,ja_word,next_review_at".to_string()), ..Default::default() };
+        assert!(!params.field_selection().unwrap().is_all());
+
+        let unknown = VocabularyListParams { fields: Some("id,meaning".to_string()), ..Default::default() };
+        assert!(unknown.field_selection().unwrap_err().starts_with("Unknown field 'meaning'"));
+
+        assert!(GetVocabularyQuery::default().field_selection().unwrap().is_all());
+    }
+
+    #[test]
+    fn test_pad_seed_entries_fills_up_to_count() {
+        let entries = pad_seed_entries(default_seed_entries(), 12);
+        assert_eq!(entries.len(), 12);
+        assert_eq!(entries[4].en_word, "friend");
+        assert_eq!(entries[5].en_word, "word_00001");
+        assert_eq!(entries[11].en_word, "word_00007");
+        assert!(entries.iter().all(|entry| entry.validate().is_ok()));
+
+        // Never truncates the configured list
+        assert_eq!(pad_seed_entries(default_seed_entries(), 3).len(), 5);
+    }
+
+    #[test]
+    fn test_vocabulary_index_buckets_non_ascii_letters() {
+        let rows = vec![
+            ("a".to_string(), 12),
+            ("b".to_string(), 3),
+            ("é".to_string(), 2),
+            ("3".to_string(), 1),
+            ("z".to_string(), 4),
+        ];
+        let index = vocabulary_index(rows);
+        let letters: Vec<(&str, i64)> = index.iter().map(|entry| (entry.letter.as_str(), entry.count)).collect();
+        assert_eq!(letters, vec![("#", 3), ("a", 12), ("b", 3), ("z", 4)]);
+
+        assert!(vocabulary_index(Vec::new()).is_empty());
+        assert_eq!(
+            serde_json::to_value(&index[1]).unwrap(),
+            serde_json::json!({"letter": "a", "count": 12})
+        );
+    }
+
+    #[test]
+    fn test_load_seed_entries_normalizes_file_entries() {
+        let path = std::env::temp_dir().join(format!("seed_{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"[{"en_word": "  ice \t cream ", "ja_word": " アイス ", "en_example": "  ", "lang": " ES ", "tags": [" Food "]}]"#)
+            .unwrap();
+        let entries = load_seed_entries(Some(&path), None);
+        std::fs::remove_file(&path).unwrap();
+
+        // Seeds skip ApiJson, so the entries must come back already normalized
+        let entry = &entries.unwrap()[0];
+        assert_eq!(entry.en_word, "ice cream");
+        assert_eq!(entry.ja_word, "アイス");
+        assert_eq!(entry.en_example, None);
+        assert_eq!(entry.lang.as_deref(), Some("es"));
+        assert_eq!(entry.tags, Some(vec!["food".to_string()]));
+    }
+
+    #[test]
+    fn test_parse_seed_entries() {
+        let json = r#"[
+            {"en_word": "river", "ja_word": "川"},
+            {"en_word": "mountain", "ja_word": "山", "en_example": "We climbed the mountain.", "ja_example": null}
+        ]"#;
+        let entries = parse_seed_entries(json).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].en_example.as_deref(), Some("We climbed the mountain."));
+
+        let not_array = parse_seed_entries(r#"{"en_word": "river"}"#).unwrap_err();
+        assert!(not_array.contains("JSON array"));
+
+        let invalid_entry = parse_seed_entries(r#"[{"en_word": "river", "ja_word": "川"}, {"en_word": "lake", "ja_word": " "}]"#)
+            .unwrap_err();
+        assert_eq!(invalid_entry, "Seed entry #1 ('lake') is invalid: Japanese word cannot be empty");
+    }
 }
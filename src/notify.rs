@@ -0,0 +1,230 @@
+// Vocabulary change notifications
+// PostgreSQL LISTEN/NOTIFY plumbing so every instance hears about vocabulary writes
+
+use postgres_native_tls::MakeTlsConnector;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch, OnceCell};
+use tokio_postgres::{AsyncMessage, Client, Notification};
+use tracing::{debug, info, warn};
+
+use crate::{db::RetryPolicy, error::ApiError};
+
+/// 語彙の変更を通知する PostgreSQL のチャンネル名。
+pub const VOCABULARY_CHANGED_CHANNEL: &str = "vocabulary_changed";
+
+/// `broadcast` チャンネルに溜められるイベント数。遅い購読者はこれを超えると `Lagged` になる。
+const CHANGE_BUFFER_SIZE: usize = 256;
+
+/// LISTEN 接続が切れたときの再接続間隔。1 秒から倍々に伸ばし、`MAX_RECONNECT_DELAY` で頭打ちにする。
+const RECONNECT_POLICY: RetryPolicy = RetryPolicy {
+    max_retries: u32::MAX,
+    base_delay: Duration::from_secs(1),
+};
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// 購読者に配信する語彙の変更イベント。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VocabularyChange {
+    /// 指定 ID の語彙が作成・更新・削除された (NOTIFY のペイロードは `<id>`)。
+    Entry(i32),
+    /// 一括更新など、どの語彙が変わったか特定しない変更 (ペイロードは `*`)。
+    All,
+    /// LISTEN 接続を張り直した。切断中の通知は失われているので、キャッシュは全て破棄すること。
+    Resync,
+}
+
+impl VocabularyChange {
+    /// NOTIFY のペイロードを解釈する。不明な形式は `None`。
+    pub fn from_payload(payload: &str) -> Option<Self> {
+        match payload.trim() {
+            "*" => Some(VocabularyChange::All),
+            id => id.parse().ok().map(VocabularyChange::Entry),
+        }
+    }
+
+    /// NOTIFY に載せるペイロード。`Resync` はプロセス内でのみ使うので送らない。
+    pub fn payload(&self) -> Option<String> {
+        match self {
+            VocabularyChange::Entry(id) => Some(id.to_string()),
+            VocabularyChange::All => Some("*".to_string()),
+            VocabularyChange::Resync => None,
+        }
+    }
+}
+
+/// LISTEN 中の接続。`client` を落とすと接続も閉じるので、通知の受信中は保持し続ける。
+struct ListenSession {
+    _client: Client,
+    notifications: mpsc::UnboundedReceiver<Notification>,
+}
+
+/// プールとは別の専用接続で `LISTEN vocabulary_changed` し、受け取った通知を `broadcast` で配る。
+/// 接続は最初の購読時に 1 本だけ張り、購読者が増えても共有する。
+/// 接続が切れた場合はバックオフしながら再接続し、成功したら `VocabularyChange::Resync` を流す。
+pub struct ChangeListener {
+    pg_config: tokio_postgres::Config,
+    tls: MakeTlsConnector,
+    sender: broadcast::Sender<VocabularyChange>,
+    started: OnceCell<()>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl ChangeListener {
+    /// 接続設定だけを保持する。実際の接続は `subscribe` まで張らない。
+    pub fn new(pg_config: tokio_postgres::Config, tls: MakeTlsConnector) -> Self {
+        let (sender, _) = broadcast::channel(CHANGE_BUFFER_SIZE);
+        let (shutdown, _) = watch::channel(false);
+        ChangeListener {
+            pg_config,
+            tls,
+            sender,
+            started: OnceCell::new(),
+            shutdown,
+        }
+    }
+
+    /// 変更イベントの受信機を返す。初回だけ LISTEN 接続を張り、失敗した場合はエラーを返す
+    /// (次の呼び出しで再度接続を試みる)。
+    pub async fn subscribe(self: &Arc<Self>) -> Result<broadcast::Receiver<VocabularyChange>, ApiError> {
+        self.started
+            .get_or_try_init(|| async {
+                let session = self.listen().await.map_err(|e| {
+                    ApiError::Database(format!("Failed to LISTEN on {}: {}", VOCABULARY_CHANGED_CHANNEL, e))
+                })?;
+                info!("Listening for {} notifications", VOCABULARY_CHANGED_CHANNEL);
+                tokio::spawn(Arc::clone(self).run(session));
+                Ok::<(), ApiError>(())
+            })
+            .await?;
+
+        Ok(self.sender.subscribe())
+    }
+
+    /// LISTEN ループを止める。`Database::close` から呼ぶ。
+    pub fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// 専用接続を張って LISTEN する。接続の駆動は別タスクで行い、通知だけを mpsc で受け取る。
+    async fn listen(&self) -> Result<ListenSession, tokio_postgres::Error> {
+        let (client, mut connection) = self.pg_config.connect(self.tls.clone()).await?;
+
+        let (sender, notifications) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                match std::future::poll_fn(|cx| connection.poll_message(cx)).await {
+                    Some(Ok(AsyncMessage::Notification(notification))) => {
+                        if sender.send(notification).is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        warn!("LISTEN connection error: {}", e);
+                        break;
+                    }
+                    None => break,
+                }
+            }
+        });
+
+        client
+            .batch_execute(&format!("LISTEN {}", VOCABULARY_CHANGED_CHANNEL))
+            .await?;
+
+        Ok(ListenSession {
+            _client: client,
+            notifications,
+        })
+    }
+
+    /// 通知を購読者に流し続ける。接続が切れたら再接続し、シャットダウンで終了する。
+    async fn run(self: Arc<Self>, mut session: ListenSession) {
+        let mut shutdown = self.shutdown.subscribe();
+        loop {
+            let notification = tokio::select! {
+                _ = shutdown.wait_for(|closed| *closed) => break,
+                notification = session.notifications.recv() => notification,
+            };
+
+            if let Some(notification) = notification {
+                self.dispatch(notification.payload());
+                continue;
+            }
+
+            warn!("LISTEN connection for {} was lost, reconnecting", VOCABULARY_CHANGED_CHANNEL);
+            match self.reconnect(&mut shutdown).await {
+                Some(new_session) => {
+                    session = new_session;
+                    // Notifications sent while disconnected are gone for good
+                    let _ = self.sender.send(VocabularyChange::Resync);
+                }
+                None => break,
+            }
+        }
+        info!("Stopped listening for {} notifications", VOCABULARY_CHANGED_CHANNEL);
+    }
+
+    /// 再接続できるまでバックオフしながら繰り返す。シャットダウンされたら `None`。
+    async fn reconnect(&self, shutdown: &mut watch::Receiver<bool>) -> Option<ListenSession> {
+        let mut attempt = 0;
+        loop {
+            let delay = RECONNECT_POLICY.delay_for(attempt).min(MAX_RECONNECT_DELAY);
+            tokio::select! {
+                _ = shutdown.wait_for(|closed| *closed) => return None,
+                _ = tokio::time::sleep(delay) => {}
+            }
+
+            match self.listen().await {
+                Ok(session) => {
+                    info!("Reconnected LISTEN connection for {}", VOCABULARY_CHANGED_CHANNEL);
+                    return Some(session);
+                }
+                Err(e) => {
+                    warn!("LISTEN reconnect attempt {} failed: {}", attempt + 1, e);
+                    attempt = attempt.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    /// ペイロードを解釈して購読者に配る。購読者がいなくても問題ない。
+    fn dispatch(&self, payload: &str) {
+        match VocabularyChange::from_payload(payload) {
+            Some(change) => {
+                debug!("Received vocabulary change: {:?}", change);
+                let _ = self.sender.send(change);
+            }
+            None => warn!("Ignoring malformed {} payload: {:?}", VOCABULARY_CHANGED_CHANNEL, payload),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_round_trip() {
+        for change in [VocabularyChange::Entry(42), VocabularyChange::All] {
+            let payload = change.payload().unwrap();
+            assert_eq!(VocabularyChange::from_payload(&payload), Some(change));
+        }
+        assert_eq!(VocabularyChange::Resync.payload(), None);
+    }
+
+    #[test]
+    fn test_malformed_payload_is_ignored() {
+        assert_eq!(VocabularyChange::from_payload(""), None);
+        assert_eq!(VocabularyChange::from_payload("apple"), None);
+        assert_eq!(VocabularyChange::from_payload(" 7 "), Some(VocabularyChange::Entry(7)));
+    }
+
+    #[test]
+    fn test_reconnect_delay_is_capped() {
+        assert_eq!(RECONNECT_POLICY.delay_for(0), Duration::from_secs(1));
+        assert_eq!(RECONNECT_POLICY.delay_for(3), Duration::from_secs(8));
+        assert_eq!(RECONNECT_POLICY.delay_for(40).min(MAX_RECONNECT_DELAY), MAX_RECONNECT_DELAY);
+    }
+}
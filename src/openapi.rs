@@ -0,0 +1,227 @@
+// OpenAPI document
+// Assembled from the `#[utoipa::path]` annotations on the handlers
+
+use utoipa::OpenApi;
+
+use crate::{
+    config::Environment,
+    db::{CircuitState, CircuitStatus, HealthReport, PoolStats},
+    error::{ErrorBody, ErrorResponse},
+    handlers::{self, admin, posts, search, users, vocabulary},
+    models::{
+        audit::AuditEntry,
+        pagination::PageInfo,
+        post::{CreatePostRequest, Post, PostWithAuthor, UpdatePostRequest},
+        review::{ReviewRequest, ReviewSchedule},
+        search::SearchResults,
+        user::{
+            CreateUserRequest, CreateUserWithPostsRequest, InitialPostRequest, UpdateUserRequest, UpdateUserResponse, User,
+            UserSummary, UserWithPostsResponse,
+        },
+        vocabulary::{
+            CoverageRequest, CoverageResponse, CreateVocabularyRequest, CreateVocabularyResponse, FavoriteRequest,
+            KnownWord, LetterCount, TagCount, UpdateVocabularyRequest, Vocabulary, VocabularyBatchResponse, VocabularyChanges,
+            VocabularyExamples, VocabularyHistoryEntry,
+        },
+    },
+};
+
+/// OpenAPI 仕様書を JSON で公開する URL。クライアント SDK の生成にはこれを使う。
+pub const OPENAPI_JSON_PATH: &str = "/api-docs/openapi.json";
+
+/// Swagger UI を配信するパス。
+pub const SWAGGER_UI_PATH: &str = "/swagger-ui";
+
+/// API 全体の OpenAPI 3 ドキュメント。
+/// ルートを追加したら、ハンドラに `#[utoipa::path]` を付けて `paths(...)` にも登録すること。
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Word REST API",
+        description = "Users, posts and English-Japanese vocabulary backed by PostgreSQL"
+    ),
+    paths(
+        handlers::health_check,
+        handlers::readiness_check,
+        handlers::build_info,
+        users::create_user,
+        users::create_user_with_posts,
+        users::get_all_users,
+        users::search_users,
+        users::get_user_by_id,
+        users::update_user,
+        users::delete_user,
+        users::get_user_posts,
+        users::transfer_posts,
+        users::verify_user_email,
+        posts::create_post,
+        posts::get_all_posts,
+        posts::get_posts_on_this_day,
+        posts::get_post_by_id,
+        posts::get_post_author,
+        posts::update_post,
+        search::search,
+        vocabulary::create_vocabulary,
+        vocabulary::get_all_vocabulary,
+        vocabulary::get_random_vocabulary,
+        vocabulary::get_vocabulary_tags,
+        vocabulary::get_vocabulary_index,
+        vocabulary::get_vocabulary_changes,
+        vocabulary::stream_vocabulary,
+        vocabulary::vocabulary_events,
+        vocabulary::get_vocabulary_coverage,
+        vocabulary::get_due_vocabulary,
+        vocabulary::get_vocabulary_batch,
+        vocabulary::get_vocabulary_by_id,
+        vocabulary::update_vocabulary,
+        vocabulary::get_vocabulary_examples,
+        vocabulary::get_vocabulary_history,
+        vocabulary::review_vocabulary,
+        vocabulary::set_vocabulary_favorite,
+        vocabulary::get_vocabulary_favorited_by,
+        vocabulary::get_related_vocabulary,
+        admin::auto_assign_vocabulary_difficulty,
+        admin::reset_vocabulary,
+        admin::get_audit_log,
+    ),
+    components(schemas(
+        User,
+        UserSummary,
+        CreateUserRequest,
+        CreateUserWithPostsRequest,
+        InitialPostRequest,
+        UserWithPostsResponse,
+        UpdateUserRequest,
+        UpdateUserResponse,
+        Post,
+        PostWithAuthor,
+        CreatePostRequest,
+        UpdatePostRequest,
+        SearchResults,
+        Vocabulary,
+        VocabularyExamples,
+        VocabularyHistoryEntry,
+        VocabularyBatchResponse,
+        TagCount,
+        LetterCount,
+        VocabularyChanges,
+        ReviewSchedule,
+        ReviewRequest,
+        FavoriteRequest,
+        CreateVocabularyRequest,
+        UpdateVocabularyRequest,
+        CreateVocabularyResponse,
+        CoverageRequest,
+        CoverageResponse,
+        KnownWord,
+        handlers::BuildInfo,
+        handlers::HealthStatus,
+        handlers::ReadinessReport,
+        HealthReport,
+        PoolStats,
+        PageInfo,
+        CircuitStatus,
+        CircuitState,
+        admin::AutoDifficultyResponse,
+        admin::ResetVocabularyResponse,
+        AuditEntry,
+        users::TransferPostsResponse,
+        ErrorResponse,
+        ErrorBody,
+    )),
+    tags(
+        (name = "users", description = "User management"),
+        (name = "posts", description = "Posts written by users"),
+        (name = "vocabulary", description = "English-Japanese vocabulary"),
+        (name = "search", description = "Search across users, posts and vocabulary"),
+        (name = "admin", description = "Maintenance operations on curated data"),
+        (name = "system", description = "Health and build information"),
+    )
+)]
+pub struct ApiDoc;
+
+/// ローカル環境でのみルーターに登録するパス。本番の仕様書には載せない。
+pub const LOCAL_ONLY_PATHS: &[&str] = &["/api/admin/vocabulary/reset", "/api/admin/audit"];
+
+/// 実行環境に合わせた OpenAPI ドキュメント。本番ではルートが存在しないローカル専用のパスを取り除く。
+pub fn api_doc(environment: &Environment) -> utoipa::openapi::OpenApi {
+    let mut spec = ApiDoc::openapi();
+    if !environment.is_local() {
+        spec.paths.paths.retain(|path, _| !LOCAL_ONLY_PATHS.contains(&path.as_str()));
+    }
+    spec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_documents_all_routes() {
+        let spec = ApiDoc::openapi();
+
+        for path in [
+            "/health",
+            "/health/ready",
+            "/info",
+            "/api/users",
+            "/api/users/search",
+            "/api/users/with-posts",
+            "/api/users/{id}",
+            "/api/users/{id}/posts",
+            "/api/users/{id}/transfer-posts/{to_id}",
+            "/api/users/{id}/verify-email",
+            "/api/posts",
+            "/api/posts/on-this-day",
+            "/api/posts/{id}",
+            "/api/posts/{id}/author",
+            "/api/search",
+            "/api/vocabulary",
+            "/api/vocabulary/random",
+            "/api/vocabulary/tags",
+            "/api/vocabulary/index",
+            "/api/vocabulary/changes",
+            "/api/vocabulary/stream",
+            "/api/vocabulary/events",
+            "/api/vocabulary/coverage",
+            "/api/vocabulary/due",
+            "/api/vocabulary/batch",
+            "/api/vocabulary/{id}",
+            "/api/vocabulary/{id}/examples",
+            "/api/vocabulary/{id}/history",
+            "/api/vocabulary/{id}/review",
+            "/api/vocabulary/{id}/favorite",
+            "/api/vocabulary/{id}/favorited-by",
+            "/api/vocabulary/{id}/related",
+            "/api/admin/vocabulary/auto-difficulty",
+            "/api/admin/vocabulary/reset",
+            "/api/admin/audit",
+        ] {
+            assert!(spec.paths.paths.contains_key(path), "{} is not documented", path);
+        }
+        assert!(spec.paths.paths["/api/vocabulary/{id}"].patch.is_some(), "PATCH /api/vocabulary/{{id}} is not documented");
+    }
+
+    #[test]
+    fn test_local_only_paths_are_left_out_of_the_production_spec() {
+        let local = api_doc(&Environment::Local);
+        let production = api_doc(&Environment::Production);
+
+        for path in LOCAL_ONLY_PATHS {
+            assert!(local.paths.paths.contains_key(*path), "{} is missing locally", path);
+            assert!(!production.paths.paths.contains_key(*path), "{} is documented in production", path);
+        }
+        assert!(production.paths.paths.contains_key("/api/admin/vocabulary/auto-difficulty"));
+    }
+
+    #[test]
+    fn test_openapi_includes_error_envelope_schema() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+
+        for schema in ["User", "Post", "Vocabulary", "CreateUserRequest", "ErrorResponse"] {
+            assert!(schemas.get(schema).is_some(), "{} schema is missing", schema);
+        }
+        assert_eq!(schemas["ErrorResponse"]["properties"]["error"]["$ref"], "#/components/schemas/ErrorBody");
+    }
+}
@@ -0,0 +1,168 @@
+// Recently served vocabulary tracking
+// Per-session memory used to bias random picks away from repeats
+
+use lru::LruCache;
+use std::{
+    collections::{HashSet, VecDeque},
+    num::NonZeroUsize,
+    sync::Mutex,
+};
+
+use crate::models::Vocabulary;
+
+/// 記憶しておくセッション数の上限。超えた分は最も使われていないセッションから捨てる。
+pub const MAX_TRACKED_SESSIONS: usize = 10_000;
+
+/// セッションごとに「直近に出題した語彙 ID」を覚えておくインメモリストア。
+/// プロセス内にしか持たないため、複数インスタンス構成ではインスタンスごとに独立している。
+pub struct RecentlyServed {
+    window: usize,
+    sessions: Mutex<LruCache<String, VecDeque<i32>>>,
+}
+
+impl RecentlyServed {
+    /// `window` はセッションごとに覚えておく直近の出題数。0 なら記録しない。
+    pub fn new(window: usize, max_sessions: usize) -> Self {
+        let capacity = NonZeroUsize::new(max_sessions).unwrap_or(NonZeroUsize::MIN);
+        RecentlyServed {
+            window,
+            sessions: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// セッションに記憶している直近の出題数の上限。
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// セッションに直近で出題した語彙 ID の集合。
+    pub fn recent_ids(&self, session_id: &str) -> HashSet<i32> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions
+            .get(session_id)
+            .map(|ids| ids.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// 出題した語彙 ID を記録し、`window` を超えた古いものから忘れる。
+    pub fn record(&self, session_id: &str, ids: impl IntoIterator<Item = i32>) {
+        if self.window == 0 {
+            return;
+        }
+
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let recent = sessions.get_or_insert_mut(session_id.to_string(), VecDeque::new);
+        for id in ids {
+            recent.push_back(id);
+        }
+        while recent.len() > self.window {
+            recent.pop_front();
+        }
+    }
+}
+
+/// ランダムに取得した候補から、直近に出題していない語彙を優先して `count` 件選ぶ。
+/// 未出題の候補が足りない場合だけ、出題済みのものを候補の順で補う。
+pub fn prefer_unseen(candidates: Vec<Vocabulary>, recent: &HashSet<i32>, count: usize) -> Vec<Vocabulary> {
+    let (mut picked, seen): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .partition(|vocabulary| !recent.contains(&vocabulary.id));
+    picked.extend(seen);
+    picked.truncate(count);
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ReviewSchedule;
+    use chrono::Utc;
+
+    fn vocabulary(id: i32) -> Vocabulary {
+        Vocabulary {
+            id,
+            en_word: format!("word{}", id),
+            ja_word: format!("単語{}", id),
+            en_example: None,
+            ja_example: None,
+            lang: "ja".to_string(),
+            tags: Vec::new(),
+            difficulty: 0,
+            favorite: false,
+            review: ReviewSchedule::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Deterministic stand-in for `ORDER BY RANDOM() LIMIT n` over ids 1..=total
+    fn random_candidates(seed: &mut u64, total: i32, limit: usize) -> Vec<Vocabulary> {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let offset = (*seed >> 33) as i32 % total;
+        (0..total)
+            .map(|i| vocabulary((i * 7 + offset) % total + 1))
+            .take(limit)
+            .collect()
+    }
+
+    fn count_repeats_within_window(picks: &[i32], window: usize) -> usize {
+        (1..picks.len())
+            .filter(|&i| picks[i.saturating_sub(window)..i].contains(&picks[i]))
+            .count()
+    }
+
+    #[test]
+    fn test_record_keeps_only_the_window() {
+        let store = RecentlyServed::new(3, 10);
+        store.record("session-a", [1, 2, 3, 4]);
+
+        assert_eq!(store.recent_ids("session-a"), HashSet::from([2, 3, 4]));
+        assert!(store.recent_ids("session-b").is_empty());
+    }
+
+    #[test]
+    fn test_least_recently_used_session_is_evicted() {
+        let store = RecentlyServed::new(3, 2);
+        store.record("a", [1]);
+        store.record("b", [2]);
+        store.record("c", [3]);
+
+        assert!(store.recent_ids("a").is_empty());
+        assert_eq!(store.recent_ids("c"), HashSet::from([3]));
+    }
+
+    #[test]
+    fn test_prefer_unseen_falls_back_to_seen_candidates() {
+        let candidates = vec![vocabulary(1), vocabulary(2), vocabulary(3)];
+        let recent = HashSet::from([1, 2]);
+
+        let ids: Vec<i32> = prefer_unseen(candidates, &recent, 2).iter().map(|v| v.id).collect();
+        assert_eq!(ids, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_session_reduces_repetition() {
+        let window = 5;
+        let store = RecentlyServed::new(window, 10);
+        let mut seed = 42;
+        let mut uniform_picks = Vec::new();
+        let mut session_picks = Vec::new();
+
+        for _ in 0..200 {
+            // Without a session: plain uniform pick
+            uniform_picks.push(random_candidates(&mut seed, 10, 1)[0].id);
+
+            // With a session: over-fetch by the window size and prefer unseen entries
+            let recent = store.recent_ids("quiz");
+            let candidates = random_candidates(&mut seed, 10, 1 + recent.len());
+            let pick = prefer_unseen(candidates, &recent, 1)[0].id;
+            store.record("quiz", [pick]);
+            session_picks.push(pick);
+        }
+
+        let uniform_repeats = count_repeats_within_window(&uniform_picks, window);
+        let session_repeats = count_repeats_within_window(&session_picks, window);
+        assert!(uniform_repeats > 0);
+        assert_eq!(session_repeats, 0);
+    }
+}
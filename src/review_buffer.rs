@@ -0,0 +1,169 @@
+// Review write-behind buffer
+// Coalesces review updates in memory so Database can flush them in one multi-row UPDATE
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Notify;
+
+use crate::models::review::ReviewSchedule;
+
+/// 復習結果のライトビハインドの設定。`REVIEW_WRITE_BEHIND=true` のときだけ有効になる。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WriteBehindConfig {
+    /// 溜まった更新を書き出す間隔
+    pub flush_interval: Duration,
+    /// この件数 (語彙数) まで溜まったら間隔を待たずに書き出す
+    pub max_pending: usize,
+}
+
+/// まだ DB に書き出していない復習結果。語彙 ID ごとに最新の状態だけを持つので、
+/// 同じ語彙を何度復習しても書き出しは 1 行で済む。
+/// 書き出しが成功するまで手元に残す (少なくとも 1 回は書き出される) ので、失敗しても次の書き出しで再送される。
+pub struct ReviewBuffer {
+    config: WriteBehindConfig,
+    pending: Mutex<HashMap<i32, ReviewSchedule>>,
+    full: Notify,
+}
+
+impl ReviewBuffer {
+    /// 空のバッファを作る。
+    pub fn new(config: WriteBehindConfig) -> Self {
+        ReviewBuffer {
+            config,
+            pending: Mutex::new(HashMap::new()),
+            full: Notify::new(),
+        }
+    }
+
+    /// 書き出しの間隔。
+    pub fn flush_interval(&self) -> Duration {
+        self.config.flush_interval
+    }
+
+    /// 語彙 `id` を評価 `grade` で復習した結果を記録し、新しい復習状態を返す。
+    /// 未書き出しの結果があればそれを、なければ DB から読んだ `stored` を起点に SM-2 を計算する。
+    /// 計算と記録をロックの中で行うので、同じ語彙への同時の復習も 1 件ずつ順に反映される。
+    pub fn record(&self, id: i32, stored: &ReviewSchedule, grade: i32, now: DateTime<Utc>) -> ReviewSchedule {
+        let mut pending = self.lock();
+        let next = pending.get(&id).unwrap_or(stored).after_review(grade, now);
+        pending.insert(id, next.clone());
+
+        if pending.len() >= self.config.max_pending {
+            self.full.notify_one();
+        }
+        next
+    }
+
+    /// 語彙 `id` の未書き出しの復習状態。
+    pub fn pending(&self, id: i32) -> Option<ReviewSchedule> {
+        self.lock().get(&id).cloned()
+    }
+
+    /// 未書き出しの語彙数。
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// 未書き出しの更新がないか。
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 書き出す分の写しを取る。バッファからはまだ消さない (`acknowledge` で消す)。
+    pub fn snapshot(&self) -> Vec<(i32, ReviewSchedule)> {
+        self.lock()
+            .iter()
+            .map(|(id, schedule)| (*id, schedule.clone()))
+            .collect()
+    }
+
+    /// 書き出しに成功した分をバッファから消す。書き出し中に再び復習された語彙は、
+    /// 新しい状態がまだ書き出されていないので残す。
+    pub fn acknowledge(&self, flushed: &[(i32, ReviewSchedule)]) {
+        let mut pending = self.lock();
+        for (id, schedule) in flushed {
+            if pending.get(id) == Some(schedule) {
+                pending.remove(id);
+            }
+        }
+    }
+
+    /// 件数が `max_pending` に達するまで待つ。書き出しタスクが間隔と並べて待つ。
+    pub async fn filled(&self) {
+        self.full.notified().await
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<i32, ReviewSchedule>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(max_pending: usize) -> ReviewBuffer {
+        ReviewBuffer::new(WriteBehindConfig { flush_interval: Duration::from_secs(1), max_pending })
+    }
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_repeated_reviews_build_on_the_pending_state() {
+        let buffer = buffer(10);
+        let stored = ReviewSchedule::default();
+
+        let first = buffer.record(1, &stored, 5, at("2024-01-01T00:00:00Z"));
+        // The stored row hasn't been written yet; the second review must start from the first
+        let second = buffer.record(1, &stored, 5, at("2024-01-02T00:00:00Z"));
+
+        assert_eq!(second.review_count, 2);
+        assert_eq!(second, first.after_review(5, at("2024-01-02T00:00:00Z")));
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.pending(1), Some(second));
+    }
+
+    #[test]
+    fn test_acknowledge_keeps_entries_reviewed_during_the_flush() {
+        let buffer = buffer(10);
+        let stored = ReviewSchedule::default();
+        buffer.record(1, &stored, 4, at("2024-01-01T00:00:00Z"));
+        buffer.record(2, &stored, 4, at("2024-01-01T00:00:00Z"));
+
+        let flushed = buffer.snapshot();
+        let newer = buffer.record(2, &stored, 1, at("2024-01-01T00:05:00Z"));
+        buffer.acknowledge(&flushed);
+
+        assert_eq!(buffer.pending(1), None);
+        assert_eq!(buffer.pending(2), Some(newer));
+    }
+
+    #[test]
+    fn test_failed_flush_leaves_entries_for_the_next_attempt() {
+        let buffer = buffer(10);
+        buffer.record(7, &ReviewSchedule::default(), 3, at("2024-01-01T00:00:00Z"));
+
+        // Snapshot taken but never acknowledged, as when the UPDATE fails
+        let _ = buffer.snapshot();
+        assert_eq!(buffer.snapshot().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reaching_max_pending_wakes_the_flusher() {
+        let buffer = buffer(2);
+        let stored = ReviewSchedule::default();
+        buffer.record(1, &stored, 4, at("2024-01-01T00:00:00Z"));
+        buffer.record(2, &stored, 4, at("2024-01-01T00:00:00Z"));
+
+        tokio::time::timeout(Duration::from_millis(100), buffer.filled())
+            .await
+            .expect("flusher should be woken once max_pending entries are buffered");
+    }
+}
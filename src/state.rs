@@ -0,0 +1,105 @@
+// Shared application state
+// Router-wide state handed to every handler
+
+use axum::extract::FromRef;
+use std::{future::Future, sync::Arc, time::Instant};
+use tokio::sync::watch;
+
+use crate::{
+    config::Config,
+    db::Database,
+    inflight::InFlightRequests,
+    recent::{RecentlyServed, MAX_TRACKED_SESSIONS},
+};
+
+/// ルーター全体で共有するステート。
+/// `FromRef` を実装しているので、既存ハンドラは `State<Arc<Database>>` のまま、
+/// 設定が必要なハンドラだけ `State<Arc<Config>>` を追加で受け取れる。
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Arc<Database>,
+    pub config: Arc<Config>,
+    pub started_at: Instant, // Used to report uptime from /info
+    pub recently_served: Arc<RecentlyServed>,
+    pub in_flight: Arc<InFlightRequests>, // Reported when shutdown gives up waiting on them
+    pub shutdown: Shutdown, // Ends responses that never finish on their own (SSE) once shutdown begins
+}
+
+/// シャットダウンの開始を、自分では終わらないレスポンス (SSE など) に知らせる。
+/// グレースフルシャットダウンは処理中のレスポンスが終わるのを待つので、これがないと SSE の接続が
+/// 1 本あるだけで猶予時間いっぱいまで待たされ、強制終了になる。
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Shutdown {
+            sender: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// シャットダウンが始まったことを知らせる。`started()` で待っている全員が起きる。
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// シャットダウンが始まったら (すでに始まっていればすぐに) 完了する Future。
+    pub fn started(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut receiver = self.sender.subscribe();
+        async move {
+            // The sender lives in this Shutdown's Arc, so wait_for only fails if every handle is gone
+            let _ = receiver.wait_for(|started| *started).await;
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppState {
+    /// DB と設定を束ねてステートを作る。起動時刻もここで記録する。
+    pub fn new(db: Arc<Database>, config: Arc<Config>) -> Self {
+        let recently_served = Arc::new(RecentlyServed::new(
+            config.random_recency_window,
+            MAX_TRACKED_SESSIONS,
+        ));
+
+        AppState {
+            db,
+            config,
+            started_at: Instant::now(),
+            recently_served,
+            in_flight: Arc::new(InFlightRequests::new()),
+            shutdown: Shutdown::new(),
+        }
+    }
+}
+
+impl FromRef<AppState> for Arc<Database> {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<RecentlyServed> {
+    fn from_ref(state: &AppState) -> Self {
+        state.recently_served.clone()
+    }
+}
+
+impl FromRef<AppState> for Shutdown {
+    fn from_ref(state: &AppState) -> Self {
+        state.shutdown.clone()
+    }
+}
@@ -0,0 +1,103 @@
+// Test support
+// Helpers for tests that need a real PostgreSQL database
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::FixedOffset;
+use tokio::sync::OnceCell;
+
+use crate::{
+    config::{Config, DatabaseConfig, EmptyListStatus, Environment, PaginationConfig, PostLimits, DEFAULT_MAX_BODY_BYTES},
+    db::Database,
+    models::{user::CreateUserRequest, vocabulary::{CreateVocabularyRequest, DifficultyHeuristic}},
+    state::AppState,
+};
+
+/// 実 DB を使うテストの接続先を指定する環境変数。未設定ならそれらのテストは何もせずに通る。
+pub const TEST_DATABASE_URL_VAR: &str = "TEST_DATABASE_URL";
+
+static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+/// `TEST_DATABASE_URL` の DB 向けの設定。レプリカは使わず、語彙キャッシュは有効にする。
+pub fn test_database_config() -> Option<DatabaseConfig> {
+    let url = std::env::var(TEST_DATABASE_URL_VAR).ok()?;
+    let mut config = DatabaseConfig::from_connection_string(&url).expect("invalid TEST_DATABASE_URL");
+    config.read_url = None;
+    config.max_retries = 0;
+    config.vocabulary_cache_capacity = 100;
+    config.review_write_behind = None;
+    Some(config)
+}
+
+/// `TEST_DATABASE_URL` の DB に接続した `Database`。マイグレーションはテストプロセスで 1 度だけ流す。
+/// 未設定なら `None` を返すので、呼び出し側は `let Some(db) = test_database().await else { return; };` で抜ける。
+/// テストは並行に走り DB も共有するので、作るデータには `unique_suffix()` を付けて他のテストとぶつからないようにする。
+pub async fn test_database() -> Option<Database> {
+    let config = test_database_config()?;
+    let db = Database::new(config).await.expect("failed to connect to TEST_DATABASE_URL");
+    MIGRATED
+        .get_or_init(|| async { db.migrate().await.expect("failed to migrate the test database") })
+        .await;
+    Some(db)
+}
+
+/// 環境変数を読まずに、`Config::from_env` の既定値で組み立てた設定。
+pub fn test_config(database: DatabaseConfig) -> Config {
+    Config {
+        port: 8080,
+        database,
+        environment: Environment::Local,
+        timezone_offset: FixedOffset::east_opt(0).unwrap(),
+        page_size_clamp_header: true,
+        pagination: PaginationConfig::default(),
+        post_limits: PostLimits::default(),
+        empty_list_status: EmptyListStatus::Ok,
+        difficulty_heuristic: "4,6,8,10".parse::<DifficultyHeuristic>().unwrap(),
+        cors_allowed_origins: Vec::new(),
+        info_endpoint_enabled: true,
+        request_timeout: Duration::from_secs(30),
+        shutdown_timeout: Duration::from_secs(25),
+        max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        random_recency_window: 20,
+        vocab_cache_max_age: Some(Duration::from_secs(60)),
+        seed_vocabulary: false,
+        vocabulary_seed_file: None,
+        seed_vocabulary_count: None,
+        log_bodies: false,
+        tls: None,
+    }
+}
+
+/// `test_database()` と `test_config` を束ねた `AppState`。ハンドラーをルーター越しに試すテスト向け。
+/// `configure` で既定の設定を書き換えられる。`TEST_DATABASE_URL` が未設定なら `None`。
+pub async fn test_app_state(configure: impl FnOnce(&mut Config)) -> Option<AppState> {
+    let db = test_database().await?;
+    let mut config = test_config(test_database_config()?);
+    configure(&mut config);
+    Some(AppState::new(Arc::new(db), Arc::new(config)))
+}
+
+/// テストデータの名前やメールアドレスに付ける、実行ごとに異なる短い文字列。
+pub fn unique_suffix() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..12].to_string()
+}
+
+/// `en_word` だけを指定した語彙登録の入力。
+pub fn vocabulary_request(en_word: &str) -> CreateVocabularyRequest {
+    CreateVocabularyRequest {
+        en_word: en_word.to_string(),
+        ja_word: "訳".to_string(),
+        en_example: None,
+        ja_example: None,
+        lang: None,
+        tags: None,
+    }
+}
+
+/// 他のテストとぶつからないメールアドレスを持つユーザー登録の入力。
+pub fn user_request(name: &str) -> CreateUserRequest {
+    CreateUserRequest {
+        name: name.to_string(),
+        email: format!("{}_{}@example.com", name, unique_suffix()),
+    }
+}